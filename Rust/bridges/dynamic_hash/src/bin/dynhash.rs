@@ -0,0 +1,386 @@
+/**
+ * Author......: See docs/credits.txt
+ * License.....: MIT
+ */
+use std::{
+    env,
+    fs::File,
+    io::{self, BufRead, BufReader, Write},
+    process,
+};
+
+use dynamic_hash::{
+    discover::{discover, Sample, SearchOptions},
+    eval::EvalContext,
+    fields::FieldSchema,
+    parse_algorithm, Expr, ExtraParams, MatchMode, OutputFormat,
+};
+
+const USAGE: &str = "Usage: dynhash <command> [args]
+
+Commands:
+  eval    [--fields SCHEMA] <expression> <password> [salt]
+                                             print the digest of one candidate
+  verify  [--fields SCHEMA] [--match MODE] <expression> <hashfile> <wordlist>
+                                             check hash*salt lines against plaintexts
+  explain <expression>                       print the canonical form and parsed tree
+  discover [--depth N] [--iterations N] <potfile>
+                                             search for expressions matching hash*salt:password lines
+
+--fields binds named salt fields, as with fields= in --bridge-parameter3.
+--match prefix|suffix accepts hashes that are the start or end of the digest,
+as with match= in --bridge-parameter3.
+Expressions may also be John the Ripper dynamic formats (dynamic_N or dynamic=...).
+Passwords, salts and wordlist entries may be given as $HEX[...].";
+
+/// Decodes hashcat's `$HEX[...]` notation, returning other input as is.
+fn decode_hex_notation(s: &[u8]) -> Vec<u8> {
+    s.strip_prefix(b"$HEX[")
+        .and_then(|s| s.strip_suffix(b"]"))
+        .and_then(|s| hex::decode(s).ok())
+        .unwrap_or_else(|| s.to_vec())
+}
+
+/// Formats a digest for display, falling back to `$HEX[...]` if it is not
+/// printable text.
+fn encode_hex_notation(data: &[u8]) -> String {
+    match std::str::from_utf8(data) {
+        Ok(s) if !s.chars().any(char::is_control) => s.to_string(),
+        _ => format!("$HEX[{}]", hex::encode(data)),
+    }
+}
+
+/// Like `encode_hex_notation`, but also encodes plaintexts containing `:` so
+/// that `hash*salt:password` lines can be split again.
+fn encode_plain(data: &[u8]) -> String {
+    if data.contains(&b':') {
+        format!("$HEX[{}]", hex::encode(data))
+    } else {
+        encode_hex_notation(data)
+    }
+}
+
+/// Splits a hash line the same way module 74000 does: the hash ends at the
+/// first `*`, the rest of the line is the salt. Like the module, a line
+/// without a `*` is rejected, even for unsalted algorithms (`hash*`).
+fn split_hash_line(line: &[u8]) -> Result<(&[u8], &[u8]), String> {
+    match line.iter().position(|&b| b == b'*') {
+        Some(pos) => Ok((&line[..pos], &line[pos + 1..])),
+        None => Err("Separator unmatched, expected hash*salt".to_string()),
+    }
+}
+
+fn read_lines(path: &str) -> io::Result<Vec<Vec<u8>>> {
+    let reader: Box<dyn BufRead> = if path == "-" {
+        Box::new(BufReader::new(io::stdin()))
+    } else {
+        Box::new(BufReader::new(File::open(path)?))
+    };
+    let mut lines = vec![];
+    for line in reader.split(b'\n') {
+        let mut line = line?;
+        if line.last() == Some(&b'\r') {
+            line.pop();
+        }
+        lines.push(line);
+    }
+    Ok(lines)
+}
+
+fn parse_expr(s: &str) -> Result<Expr, String> {
+    parse_algorithm(s).map_err(|e| format!("failed to parse expression: {}", e))
+}
+
+/// Removes `--fields SCHEMA` from the arguments and parses the schema.
+fn take_fields(args: &[String]) -> Result<(Vec<String>, Option<FieldSchema>), String> {
+    let mut rest = vec![];
+    let mut fields = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--fields" {
+            let schema = args.next().ok_or("--fields expects a schema")?;
+            fields = Some(FieldSchema::parse(schema)?);
+        } else {
+            rest.push(arg.clone());
+        }
+    }
+    Ok((rest, fields))
+}
+
+/// Removes `--match MODE` from the arguments and parses the mode.
+fn take_match(args: Vec<String>) -> Result<(Vec<String>, MatchMode), String> {
+    let mut rest = vec![];
+    let mut mode = MatchMode::default();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if arg == "--match" {
+            let value = args.next().ok_or("--match expects a mode")?;
+            mode = MatchMode::parse(&value)?;
+        } else {
+            rest.push(arg);
+        }
+    }
+    Ok((rest, mode))
+}
+
+fn bind_salt(
+    ctx: &mut EvalContext,
+    fields: Option<&FieldSchema>,
+    salt: &[u8],
+) -> Result<(), String> {
+    match fields {
+        Some(fields) => fields.bind(ctx, salt),
+        None => {
+            ctx.set_salt(salt);
+            Ok(())
+        }
+    }
+}
+
+fn cmd_eval(args: &[String]) -> Result<(), String> {
+    let (args, fields) = take_fields(args)?;
+    let [expr, password, rest @ ..] = args.as_slice() else {
+        return Err(USAGE.to_string());
+    };
+    if rest.len() > 1 {
+        return Err(USAGE.to_string());
+    }
+    let ast = parse_expr(expr)?;
+    let mut ctx = EvalContext::new();
+    ctx.set_var("p", decode_hex_notation(password.as_bytes()));
+    if let Some(salt) = rest.first() {
+        bind_salt(
+            &mut ctx,
+            fields.as_ref(),
+            &decode_hex_notation(salt.as_bytes()),
+        )?;
+    }
+    let hash = ctx.eval(&ast)?;
+    println!("{}", encode_hex_notation(&hash));
+    Ok(())
+}
+
+fn cmd_verify(args: &[String]) -> Result<(), String> {
+    let (args, fields) = take_fields(args)?;
+    let (args, match_mode) = take_match(args)?;
+    let [expr, hashfile, wordlist] = args.as_slice() else {
+        return Err(USAGE.to_string());
+    };
+    let ast = parse_expr(expr)?;
+    let hashes = read_lines(hashfile).map_err(|e| format!("{}: {}", hashfile, e))?;
+    let words = read_lines(wordlist).map_err(|e| format!("{}: {}", wordlist, e))?;
+    let words: Vec<_> = words.iter().map(|w| decode_hex_notation(w)).collect();
+
+    let mut stdout = io::stdout().lock();
+    let mut total = 0;
+    let mut cracked = 0;
+    for (lineno, line) in hashes.iter().enumerate() {
+        if line.is_empty() {
+            continue;
+        }
+        total += 1;
+        let (hash, salt) =
+            split_hash_line(line).map_err(|e| format!("{}:{}: {}", hashfile, lineno + 1, e))?;
+        let mut ctx = EvalContext::new();
+        bind_salt(&mut ctx, fields.as_ref(), salt)
+            .map_err(|e| format!("{}:{}: {}", hashfile, lineno + 1, e))?;
+        for word in &words {
+            ctx.set_var("p", word);
+            let mut digest = ctx
+                .eval(&ast)
+                .map_err(|e| format!("{}:{}: {}", hashfile, lineno + 1, e))?;
+            match_mode.apply(&mut digest, hash.len());
+            if digest == hash {
+                cracked += 1;
+                writeln!(
+                    stdout,
+                    "{}:{}",
+                    String::from_utf8_lossy(line),
+                    encode_plain(word)
+                )
+                .map_err(|e| e.to_string())?;
+                break;
+            }
+        }
+    }
+    eprintln!("Recovered: {}/{}", cracked, total);
+    Ok(())
+}
+
+fn explain_expr(out: &mut String, label: &str, expr: &Expr, depth: usize) {
+    let indent = "  ".repeat(depth);
+    let label = if label.is_empty() {
+        String::new()
+    } else {
+        format!("{}: ", label)
+    };
+    match expr {
+        Expr::Call {
+            name,
+            arg,
+            params,
+            output_format,
+        } => {
+            let format = match output_format {
+                OutputFormat::Default => "",
+                OutputFormat::Hex => " [hex]",
+                OutputFormat::Binary => " [binary]",
+                OutputFormat::Base64 => " [base64]",
+            };
+            out.push_str(&format!("{}{}call {}{}\n", indent, label, name, format));
+            match params {
+                Some(ExtraParams::Key(key)) => explain_expr(out, "key", key, depth + 1),
+                Some(ExtraParams::StartLength(start, length)) => out.push_str(&format!(
+                    "{}  start: {}\n{}  length: {}\n",
+                    indent, start, indent, length
+                )),
+                Some(ExtraParams::CostSalt(cost, salt)) => {
+                    explain_expr(out, "cost", cost, depth + 1);
+                    explain_expr(out, "salt", salt, depth + 1);
+                }
+                Some(ExtraParams::RoundsSaltDklen(rounds, salt, dklen)) => {
+                    explain_expr(out, "rounds", rounds, depth + 1);
+                    explain_expr(out, "salt", salt, depth + 1);
+                    explain_expr(out, "dklen", dklen, depth + 1);
+                }
+                None => {}
+            }
+            explain_expr(out, "arg", arg, depth + 1);
+        }
+        Expr::Concat(exprs) => {
+            out.push_str(&format!("{}{}concat\n", indent, label));
+            for expr in exprs {
+                explain_expr(out, "", expr, depth + 1);
+            }
+        }
+        Expr::Var(_) | Expr::Literal(_) | Expr::File { .. } | Expr::Number(_) => {
+            let kind = match expr {
+                Expr::Var(_) => "var",
+                Expr::Literal(_) => "literal",
+                Expr::File { .. } => "file",
+                _ => "number",
+            };
+            out.push_str(&format!("{}{}{} {}\n", indent, label, kind, expr));
+        }
+    }
+}
+
+fn cmd_explain(args: &[String]) -> Result<(), String> {
+    let [expr] = args else {
+        return Err(USAGE.to_string());
+    };
+    let ast = parse_expr(expr)?;
+    let mut out = format!("{}\n\n", ast);
+    explain_expr(&mut out, "", &ast, 0);
+    print!("{}", out);
+    Ok(())
+}
+
+fn cmd_discover(args: &[String]) -> Result<(), String> {
+    let mut options = SearchOptions::default();
+    let mut potfile = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--depth" | "--iterations" => {
+                let value = args
+                    .next()
+                    .and_then(|v| v.parse().ok())
+                    .ok_or_else(|| format!("{} expects a number", arg))?;
+                if arg == "--depth" {
+                    options.max_depth = value;
+                } else {
+                    options.max_iterations = value;
+                }
+            }
+            _ if potfile.is_none() => potfile = Some(arg),
+            _ => return Err(USAGE.to_string()),
+        }
+    }
+    let Some(potfile) = potfile else {
+        return Err(USAGE.to_string());
+    };
+
+    let mut samples = vec![];
+    for (lineno, line) in read_lines(potfile)
+        .map_err(|e| format!("{}: {}", potfile, e))?
+        .iter()
+        .enumerate()
+    {
+        if line.is_empty() {
+            continue;
+        }
+        let pos = line
+            .iter()
+            .rposition(|&b| b == b':')
+            .ok_or_else(|| format!("{}:{}: expected hash*salt:password", potfile, lineno + 1))?;
+        let (hash, salt) = split_hash_line(&line[..pos])
+            .map_err(|e| format!("{}:{}: {}", potfile, lineno + 1, e))?;
+        samples.push(Sample {
+            password: decode_hex_notation(&line[pos + 1..]),
+            salt: salt.to_vec(),
+            hash: hash.to_vec(),
+        });
+    }
+
+    let found = discover(&samples, &options);
+    for expr in &found {
+        println!("{}", expr);
+    }
+    eprintln!("Found: {}", found.len());
+    Ok(())
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let result = match args.first().map(String::as_str) {
+        Some("eval") => cmd_eval(&args[1..]),
+        Some("verify") => cmd_verify(&args[1..]),
+        Some("explain") => cmd_explain(&args[1..]),
+        Some("discover") => cmd_discover(&args[1..]),
+        Some("-h" | "--help" | "help") => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        _ => Err(USAGE.to_string()),
+    };
+    if let Err(e) = result {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hex_notation() {
+        assert_eq!(decode_hex_notation(b"$HEX[717765727479]"), b"qwerty");
+        assert_eq!(decode_hex_notation(b"$HEX[7177zz]"), b"$HEX[7177zz]");
+        assert_eq!(decode_hex_notation(b"qwerty"), b"qwerty");
+        assert_eq!(encode_hex_notation(b"qwerty"), "qwerty");
+        assert_eq!(encode_hex_notation(b"\x00\xff"), "$HEX[00ff]");
+    }
+
+    #[test]
+    fn test_split_hash_line() {
+        assert_eq!(
+            split_hash_line(b"abc*s1*s2"),
+            Ok((&b"abc"[..], &b"s1*s2"[..]))
+        );
+        assert_eq!(split_hash_line(b"abc*"), Ok((&b"abc"[..], &b""[..])));
+        assert!(split_hash_line(b"abc").is_err());
+    }
+
+    #[test]
+    fn test_explain() {
+        let ast = parse_algorithm(r#"md5:hex($p."x").cut(0,2,$s)"#).unwrap();
+        let mut out = String::new();
+        explain_expr(&mut out, "", &ast, 0);
+        assert_eq!(
+            out,
+            "concat\n  call md5 [hex]\n    arg: concat\n      var $p\n      literal \"x\"\n  call cut\n    start: 0\n    length: 2\n    arg: var $s\n"
+        );
+    }
+}
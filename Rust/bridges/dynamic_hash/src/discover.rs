@@ -0,0 +1,295 @@
+/**
+ * Author......: See docs/credits.txt
+ * License.....: MIT
+ */
+use std::collections::HashSet;
+
+use base64::{prelude::BASE64_STANDARD, Engine};
+
+use crate::{eval::EvalContext, DataDecoder, Expr, ExtraParams, OutputFormat};
+
+const DIGESTS: &[&str] = &[
+    "md2", "md4", "md5", "sha1", "sha224", "sha256", "sha384", "sha512", "sha3_224", "sha3_256",
+    "sha3_384", "sha3_512",
+];
+
+const HMAC_DIGESTS: &[&str] = &[
+    "md5", "sha1", "sha224", "sha256", "sha384", "sha512", "sha3_224", "sha3_256", "sha3_384",
+    "sha3_512",
+];
+
+/// Name of the variable that stands for the subexpression a template is
+/// applied to. It can't clash with user variables because the parser only
+/// accepts alphanumeric names.
+const INNER: &str = "#inner";
+
+/// A known password, salt and the hash the target application stored for
+/// them.
+pub struct Sample {
+    pub password: Vec<u8>,
+    pub salt: Vec<u8>,
+    pub hash: Vec<u8>,
+}
+
+pub struct SearchOptions {
+    /// Maximum number of nested digest or HMAC calls.
+    pub max_depth: usize,
+    /// Maximum number of times a digest is applied to its own hex output
+    /// within a single level, e.g. 3 finds `md5(md5(md5($p)))` at depth 1.
+    pub max_iterations: usize,
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        Self {
+            max_depth: 2,
+            max_iterations: 1,
+        }
+    }
+}
+
+struct Candidate {
+    expr: Expr,
+    value: Vec<u8>,
+    /// Whether the expression is the bare password, so that arrangements
+    /// which repeat `$p` are not tried on it.
+    is_password: bool,
+}
+
+fn var(name: &str) -> Expr {
+    Expr::Var((name.to_string(), DataDecoder::None))
+}
+
+fn call(name: &str, arg: Expr, params: Option<ExtraParams>, output_format: OutputFormat) -> Expr {
+    Expr::Call {
+        name: name.to_string(),
+        arg: Box::new(arg),
+        params,
+        output_format,
+    }
+}
+
+fn with_output_format(expr: &Expr, format: OutputFormat) -> Expr {
+    match expr {
+        Expr::Call {
+            name, arg, params, ..
+        } => Expr::Call {
+            name: name.clone(),
+            arg: arg.clone(),
+            params: params.clone(),
+            output_format: format,
+        },
+        other => other.clone(),
+    }
+}
+
+/// Replaces every reference to the `INNER` placeholder with `inner`.
+fn substitute(template: &Expr, inner: &Expr) -> Expr {
+    match template {
+        Expr::Var((name, _)) if name == INNER => inner.clone(),
+        Expr::Call {
+            name,
+            arg,
+            params,
+            output_format,
+        } => Expr::Call {
+            name: name.clone(),
+            arg: Box::new(substitute(arg, inner)),
+            params: params.as_ref().map(|params| match params {
+                ExtraParams::Key(key) => ExtraParams::Key(Box::new(substitute(key, inner))),
+                ExtraParams::StartLength(start, length) => {
+                    ExtraParams::StartLength(*start, *length)
+                }
+                ExtraParams::CostSalt(cost, salt) => ExtraParams::CostSalt(
+                    Box::new(substitute(cost, inner)),
+                    Box::new(substitute(salt, inner)),
+                ),
+                ExtraParams::RoundsSaltDklen(rounds, salt, dklen) => ExtraParams::RoundsSaltDklen(
+                    Box::new(substitute(rounds, inner)),
+                    Box::new(substitute(salt, inner)),
+                    Box::new(substitute(dklen, inner)),
+                ),
+            }),
+            output_format: *output_format,
+        },
+        Expr::Concat(exprs) => Expr::Concat(exprs.iter().map(|e| substitute(e, inner)).collect()),
+        other => other.clone(),
+    }
+}
+
+/// Templates that wrap the placeholder in one digest or HMAC call, producing
+/// binary output. Encodings are derived from the binary value afterwards.
+/// Salt arrangements are left out for unsalted samples, where they would only
+/// produce duplicates.
+fn step_templates(with_salt: bool, with_password: bool) -> Vec<Expr> {
+    let x = || var(INNER);
+    let s = || var("s");
+    let p = || var("p");
+    let mut templates = vec![];
+    for &digest in DIGESTS {
+        let salt_hash = || call(digest, s(), None, OutputFormat::Default);
+        let mut args = vec![x()];
+        if with_salt {
+            args.push(Expr::Concat(vec![x(), s()]));
+            args.push(Expr::Concat(vec![s(), x()]));
+            args.push(Expr::Concat(vec![s(), x(), s()]));
+            args.push(Expr::Concat(vec![salt_hash(), x()]));
+            args.push(Expr::Concat(vec![x(), salt_hash()]));
+        }
+        if with_password {
+            args.push(Expr::Concat(vec![x(), p()]));
+            args.push(Expr::Concat(vec![p(), x()]));
+        }
+        for arg in args {
+            templates.push(call(digest, arg, None, OutputFormat::Binary));
+        }
+    }
+    if !with_salt {
+        return templates;
+    }
+    for &digest in HMAC_DIGESTS {
+        let name = format!("hmac_{}", digest);
+        templates.push(call(
+            &name,
+            x(),
+            Some(ExtraParams::Key(Box::new(s()))),
+            OutputFormat::Binary,
+        ));
+        templates.push(call(
+            &name,
+            s(),
+            Some(ExtraParams::Key(Box::new(x()))),
+            OutputFormat::Binary,
+        ));
+    }
+    templates
+}
+
+/// Searches for expressions that reproduce the hash of every sample.
+///
+/// The search is breadth first over nested digest and HMAC calls, each level
+/// wrapping the previous one in a new call with one of a fixed set of
+/// salt/password arrangements. Intermediate results are used in hex and
+/// binary form, final results are compared as lowercase hex, uppercase hex
+/// and base64. Candidates are only evaluated against the first sample; every
+/// hit is then checked against all samples by evaluating the full expression.
+pub fn discover(samples: &[Sample], options: &SearchOptions) -> Vec<Expr> {
+    let Some(first) = samples.first() else {
+        return vec![];
+    };
+
+    let mut ctx = EvalContext::new();
+    ctx.set_var("p", &first.password);
+    ctx.set_salt(&first.salt);
+
+    let mut found = vec![];
+    let mut seen = HashSet::new();
+    let mut report = |expr: Expr| {
+        let all_match = samples.iter().all(|sample| {
+            let mut ctx = EvalContext::new();
+            ctx.set_var("p", &sample.password);
+            ctx.set_salt(&sample.salt);
+            ctx.eval(&expr).is_ok_and(|hash| hash == sample.hash)
+        });
+        if all_match && seen.insert(expr.to_string()) {
+            found.push(expr);
+        }
+    };
+
+    let mut level: Vec<Candidate> = [
+        var("p"),
+        call("utf16le", var("p"), None, OutputFormat::Default),
+    ]
+    .into_iter()
+    .map(|expr| Candidate {
+        value: ctx.eval(&expr).unwrap_or_default(),
+        expr,
+        is_password: true,
+    })
+    .collect();
+
+    let with_salt = samples.iter().any(|sample| !sample.salt.is_empty());
+    let templates = step_templates(with_salt, false);
+    let templates_with_password = step_templates(with_salt, true);
+
+    for depth in 1..=options.max_depth {
+        let mut next = vec![];
+        for candidate in &level {
+            ctx.set_var(INNER, &candidate.value);
+            let templates = if candidate.is_password {
+                &templates
+            } else {
+                &templates_with_password
+            };
+            for template in templates {
+                let Ok(binary) = ctx.eval(template) else {
+                    continue;
+                };
+                let expr = substitute(template, &candidate.expr);
+                let hex_value = hex::encode(&binary).into_bytes();
+
+                if hex_value == first.hash {
+                    report(with_output_format(&expr, OutputFormat::Default));
+                }
+                if hex_value.to_ascii_uppercase() == first.hash {
+                    report(call(
+                        "upper",
+                        with_output_format(&expr, OutputFormat::Default),
+                        None,
+                        OutputFormat::Default,
+                    ));
+                }
+                if BASE64_STANDARD.encode(&binary).as_bytes() == first.hash {
+                    report(with_output_format(&expr, OutputFormat::Base64));
+                }
+
+                let mut repeated = with_output_format(&expr, OutputFormat::Default);
+                let mut repeated_value = hex_value.clone();
+                if let Expr::Call {
+                    name, params: None, ..
+                } = &expr
+                {
+                    let template = call(name, var(INNER), None, OutputFormat::Default);
+                    for _ in 1..options.max_iterations {
+                        ctx.set_var(INNER, &repeated_value);
+                        let Ok(value) = ctx.eval(&template) else {
+                            break;
+                        };
+                        repeated = substitute(&template, &repeated);
+                        repeated_value = value;
+                        if repeated_value == first.hash {
+                            report(repeated.clone());
+                        }
+                        if repeated_value.to_ascii_uppercase() == first.hash {
+                            report(call("upper", repeated.clone(), None, OutputFormat::Default));
+                        }
+                        if depth < options.max_depth {
+                            next.push(Candidate {
+                                expr: repeated.clone(),
+                                value: repeated_value.clone(),
+                                is_password: false,
+                            });
+                        }
+                    }
+                    ctx.set_var(INNER, &candidate.value);
+                }
+
+                if depth < options.max_depth {
+                    next.push(Candidate {
+                        expr: with_output_format(&expr, OutputFormat::Default),
+                        value: hex_value,
+                        is_password: false,
+                    });
+                    next.push(Candidate {
+                        expr,
+                        value: binary,
+                        is_password: false,
+                    });
+                }
+            }
+        }
+        level = next;
+    }
+
+    found
+}
@@ -0,0 +1,103 @@
+/**
+ * Author......: See docs/credits.txt
+ * License.....: MIT
+ */
+use std::fmt;
+
+use crate::{
+    parse::{canonical_name, canonical_var_name},
+    DataDecoder, Expr, ExtraParams, OutputFormat,
+};
+
+impl fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OutputFormat::Default => Ok(()),
+            OutputFormat::Hex => write!(f, ":hex"),
+            OutputFormat::Binary => write!(f, ":bin"),
+            OutputFormat::Base64 => write!(f, ":base64"),
+        }
+    }
+}
+
+impl fmt::Display for DataDecoder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DataDecoder::None => Ok(()),
+            DataDecoder::Unhex => write!(f, ":unhex"),
+            DataDecoder::B64Decode => write!(f, ":b64decode"),
+        }
+    }
+}
+
+impl fmt::Display for ExtraParams {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExtraParams::Key(key) => write!(f, "key={},", key),
+            ExtraParams::StartLength(start, length) => write!(f, "{},{},", start, length),
+            ExtraParams::CostSalt(cost, salt) => write!(f, "cost={},salt={},", cost, salt),
+            ExtraParams::RoundsSaltDklen(rounds, salt, dklen) => {
+                write!(f, "rounds={},salt={},dklen={},", rounds, salt, dklen)
+            }
+        }
+    }
+}
+
+/// Writes `data` as a string literal that the parser reads back byte for byte.
+fn write_literal(f: &mut fmt::Formatter<'_>, data: &[u8]) -> fmt::Result {
+    f.write_str("\"")?;
+    for &b in data {
+        match b {
+            b'\n' => f.write_str("\\n")?,
+            b'\r' => f.write_str("\\r")?,
+            b'\t' => f.write_str("\\t")?,
+            b'\\' => f.write_str("\\\\")?,
+            b'"' => f.write_str("\\\"")?,
+            0 => f.write_str("\\0")?,
+            0x20..=0x7e => write!(f, "{}", b as char)?,
+            _ => write!(f, "\\x{:02x}", b)?,
+        }
+    }
+    f.write_str("\"")
+}
+
+/// Prints the canonical syntax of an expression. Aliases are replaced by
+/// their canonical names, whitespace is dropped and string literals are
+/// escaped, so the output parses back into an identical tree.
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Call {
+                name,
+                arg,
+                params,
+                output_format,
+            } => {
+                write!(f, "{}{}(", canonical_name(name), output_format)?;
+                if let Some(params) = params {
+                    write!(f, "{}", params)?;
+                }
+                write!(f, "{})", arg)
+            }
+            Expr::Concat(exprs) => {
+                for (i, expr) in exprs.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(".")?;
+                    }
+                    write!(f, "{}", expr)?;
+                }
+                Ok(())
+            }
+            Expr::Var((name, decoder)) => {
+                write!(f, "${}{}", canonical_var_name(name), decoder)
+            }
+            Expr::Literal(data) => write_literal(f, data),
+            Expr::File { path, .. } => {
+                f.write_str("file(")?;
+                write_literal(f, path.as_bytes())?;
+                f.write_str(")")
+            }
+            Expr::Number(n) => write!(f, "{}", n),
+        }
+    }
+}
@@ -15,6 +15,17 @@ use sha1::Sha1;
 use sha2::{Sha224, Sha256, Sha384, Sha512};
 use sha3::{Sha3_224, Sha3_256, Sha3_384, Sha3_512};
 
+impl DataDecoder {
+    pub fn decode(&self, data: Vec<u8>) -> Result<Vec<u8>, String> {
+        Ok(match self {
+            DataDecoder::None => data,
+            DataDecoder::Unhex => hex::decode(data).map_err(|e| e.to_string())?,
+            DataDecoder::B64Decode => BASE64_STANDARD.decode(data).map_err(|e| e.to_string())?,
+        })
+    }
+}
+
+#[derive(Default)]
 pub struct EvalContext(HashMap<String, Vec<u8>>);
 
 impl EvalContext {
@@ -22,6 +33,17 @@ impl EvalContext {
         Self(HashMap::new())
     }
 
+    /// Binds `$s` to the whole salt and, if it contains `*`, `$s1..$sN` to
+    /// its fields.
+    pub fn set_salt(&mut self, salt: &[u8]) {
+        self.set_var("s", salt);
+        if salt.contains(&b'*') {
+            for (i, s) in salt.split(|&b| b == b'*').enumerate() {
+                self.set_var(format!("s{}", i + 1), s);
+            }
+        }
+    }
+
     pub fn set_var(&mut self, name: impl AsRef<str>, value: impl AsRef<[u8]>) {
         self.0
             .insert(String::from(name.as_ref()), Vec::from(value.as_ref()));
@@ -33,7 +55,7 @@ impl EvalContext {
             "salt" => "s",
             other => other,
         };
-        self.0.get(name).map(Vec::clone)
+        self.0.get(name).cloned()
     }
 
     fn eval_number(&self, expr: &Expr) -> Result<u32, String> {
@@ -258,16 +280,10 @@ impl EvalContext {
                 let data = self
                     .var(name)
                     .ok_or_else(|| format!("Undefined variable '{}'", name))?;
-                Ok(match decoder {
-                    DataDecoder::None => data,
-                    DataDecoder::Unhex => hex::decode(data).map_err(|e| e.to_string())?,
-                    DataDecoder::B64Decode => {
-                        BASE64_STANDARD.decode(data).map_err(|e| e.to_string())?
-                    }
-                })
+                decoder.decode(data)
             }
 
-            Expr::Literal(v) => Ok(v.clone()),
+            Expr::Literal(v) | Expr::File { data: v, .. } => Ok(v.clone()),
 
             Expr::Number(_) => unreachable!(),
         }
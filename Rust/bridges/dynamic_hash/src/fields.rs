@@ -0,0 +1,106 @@
+/**
+ * Author......: See docs/credits.txt
+ * License.....: MIT
+ */
+use crate::{eval::EvalContext, parse::canonical_var_name, phc::PhcString, DataDecoder};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum FieldDecoder {
+    Data(DataDecoder),
+    /// Parses the field as a PHC or modular crypt string, see `PhcString`.
+    Phc,
+}
+
+/// Describes how the salt part of a hash line splits into named fields, e.g.
+/// `user:salt(hex):iter`. The first character that can't be part of a field
+/// name is the separator; each field may name a decoder in parentheses.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FieldSchema {
+    separator: u8,
+    fields: Vec<(String, FieldDecoder)>,
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+impl FieldSchema {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let separator = s
+            .chars()
+            .find(|&c| !is_name_char(c) && c != '(' && c != ')')
+            .unwrap_or('*');
+        if !separator.is_ascii() || separator == ';' {
+            return Err(format!("Invalid field separator '{}'", separator));
+        }
+
+        let mut fields: Vec<(String, FieldDecoder)> = vec![];
+        for field in s.split(separator) {
+            let (name, decoder) = match field.split_once('(') {
+                Some((name, decoder)) => {
+                    let decoder = match decoder.strip_suffix(')') {
+                        Some("hex" | "unhex") => FieldDecoder::Data(DataDecoder::Unhex),
+                        Some("b64" | "base64" | "b64dec" | "b64decode") => {
+                            FieldDecoder::Data(DataDecoder::B64Decode)
+                        }
+                        Some("phc") => FieldDecoder::Phc,
+                        _ => return Err(format!("Unsupported decoder in field '{}'", field)),
+                    };
+                    (name, decoder)
+                }
+                None => (field, FieldDecoder::Data(DataDecoder::None)),
+            };
+            if name.is_empty() || !name.chars().all(is_name_char) {
+                return Err(format!("Invalid field name '{}'", name));
+            }
+            let name = canonical_var_name(name);
+            if name == "p" {
+                return Err("The password can't be a salt field".to_string());
+            }
+            if fields.iter().any(|(n, _)| n == name) {
+                return Err(format!("Duplicate field '{}'", name));
+            }
+            fields.push((name.to_string(), decoder));
+        }
+
+        Ok(Self {
+            separator: separator as u8,
+            fields,
+        })
+    }
+
+    /// Binds `$s` to the whole salt and every field to its own variable. The
+    /// last field takes the rest of the salt, separators included. A field
+    /// named `s` or `salt` replaces the whole salt as `$s`, and so does the
+    /// decoded salt of a `phc` field.
+    pub fn bind(&self, ctx: &mut EvalContext, salt: &[u8]) -> Result<(), String> {
+        ctx.set_var("s", salt);
+        let mut values = salt.splitn(self.fields.len(), |&b| b == self.separator);
+        for (name, decoder) in &self.fields {
+            let value = values.next().ok_or_else(|| {
+                format!(
+                    "Salt has fewer than {} '{}'-separated fields",
+                    self.fields.len(),
+                    self.separator as char
+                )
+            })?;
+            match decoder {
+                FieldDecoder::Data(decoder) => {
+                    let value = decoder
+                        .decode(value.to_vec())
+                        .map_err(|e| format!("Field '{}': {}", name, e))?;
+                    ctx.set_var(name, value);
+                }
+                FieldDecoder::Phc => {
+                    ctx.set_var(name, value);
+                    let phc = std::str::from_utf8(value)
+                        .map_err(|e| e.to_string())
+                        .and_then(PhcString::parse)
+                        .map_err(|e| format!("Field '{}': {}", name, e))?;
+                    phc.bind(ctx);
+                }
+            }
+        }
+        Ok(())
+    }
+}
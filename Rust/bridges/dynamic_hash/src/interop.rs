@@ -2,231 +2,221 @@
  * Author......: See docs/credits.txt
  * License.....: MIT
  */
-use std::{
-    cell::OnceCell,
-    ffi::{c_char, c_int, c_void, CStr},
-    mem,
-    path::Path,
-    process, ptr, slice,
-    sync::{Once, OnceLock},
+use hashcat_bridge::{
+    export_bridge, Bridge, GlobalContext, OptionSpec, Output, Salt, ThreadContext,
 };
 
-use hashcat_sys::{bridge_context_t, generic_io_t, generic_io_tmp_t, salt_t};
+use std::{env, fs};
 
-use crate::{eval::EvalContext, parse, Expr};
-
-thread_local! {
-    static AST: OnceCell<Expr> = OnceCell::new();
-}
-
-static LOG_ERROR_ONCE: Once = Once::new();
-
-static INFO: OnceLock<&'static str> = OnceLock::new();
-
-#[repr(C)]
-pub(crate) struct ThreadContext {
-    pub module_name: String,
-
-    pub salts: Vec<salt_t>,
-    pub esalts: Vec<generic_io_t>,
+use crate::{
+    eval::EvalContext, fields::FieldSchema, parse::canonical_var_name, parse_algorithm, Expr,
+    MatchMode,
+};
 
-    pub bridge_parameter1: String,
-    pub bridge_parameter2: String,
-    pub bridge_parameter3: String,
-    pub bridge_parameter4: String,
+#[derive(Debug, Default)]
+pub(crate) struct Options {
+    fields: Option<FieldSchema>,
+    match_mode: MatchMode,
+    vars: Vec<(String, Vec<u8>)>,
 }
 
-impl ThreadContext {
-    fn get_raw_esalt(&self, salt_id: usize) -> &generic_io_t {
-        &self.esalts[salt_id]
-    }
-}
-
-unsafe fn vec_from_raw_parts<T: Clone>(data: *const T, length: c_int) -> Vec<T> {
-    if data.is_null() {
-        vec![]
+pub(crate) const OPTIONS: &[OptionSpec] = &[
+    OptionSpec {
+        name: "fields",
+        help: "names of the '*'-separated salt fields, e.g. user*salt(hex)",
+    },
+    OptionSpec {
+        name: "match",
+        help: "exact, prefix or suffix",
+    },
+];
+
+/// Loads the value of a variable option: `@path` reads a file, `env:NAME` an
+/// environment variable, anything else is used as is.
+pub(crate) fn load_secret(value: &str) -> Result<Vec<u8>, String> {
+    if let Some(path) = value.strip_prefix('@') {
+        fs::read(path).map_err(|e| format!("failed to read '{}': {}", path, e))
+    } else if let Some(name) = value.strip_prefix("env:") {
+        env::var(name)
+            .map(String::into_bytes)
+            .map_err(|e| format!("environment variable '{}': {}", name, e))
     } else {
-        Vec::from(unsafe { slice::from_raw_parts(data, length as usize) })
+        Ok(value.as_bytes().to_vec())
     }
 }
 
-unsafe fn string_from_ptr(ptr: *const c_char) -> String {
-    if ptr.is_null() {
-        String::new()
-    } else {
-        unsafe { CStr::from_ptr(ptr).to_str().unwrap_or_default().to_string() }
+/// Parses the bridge options. A variable option the algorithm never reads is
+/// rejected, since it is most likely a misspelled option like `mach=prefix`.
+pub(crate) fn parse_options(
+    options: &hashcat_bridge::Options,
+    ast: &Expr,
+) -> Result<Options, String> {
+    let mut parsed = Options::default();
+    if let Some(fields) = options.get("fields") {
+        parsed.fields =
+            Some(FieldSchema::parse(fields).map_err(|e| format!("option 'fields': {}", e))?);
+    }
+    if let Some(mode) = options.get("match") {
+        parsed.match_mode = MatchMode::parse(mode).map_err(|e| format!("option 'match': {}", e))?;
+    }
+    for (name, value) in options.iter() {
+        if name == "fields" || name == "match" {
+            continue;
+        }
+        if !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return Err(format!("option '{}' is not a valid variable name", name));
+        }
+        if matches!(canonical_var_name(name), "p" | "s") {
+            return Err(format!("option '{}' would hide the password or salt", name));
+        }
+        if !ast.references(name) {
+            return Err(format!(
+                "unknown option '{}', and the algorithm has no variable ${}",
+                name, name
+            ));
+        }
+        let value = load_secret(value).map_err(|e| format!("option '{}': {}", name, e))?;
+        parsed.vars.push((name.to_string(), value));
     }
+    Ok(parsed)
 }
 
-#[unsafe(no_mangle)]
-pub extern "C" fn new_context(
-    module_name: *const c_char,
-
-    salts_cnt: c_int,
-    salts_size: c_int,
-    salts_buf: *const c_char,
-
-    esalts_cnt: c_int,
-    esalts_size: c_int,
-    esalts_buf: *const c_char,
-
-    _st_salts_cnt: c_int,
-    _st_salts_size: c_int,
-    _st_salts_buf: *const c_char,
-
-    _st_esalts_cnt: c_int,
-    _st_esalts_size: c_int,
-    _st_esalts_buf: *const c_char,
-
-    bridge_parameter1: *const c_char,
-    bridge_parameter2: *const c_char,
-    bridge_parameter3: *const c_char,
-    bridge_parameter4: *const c_char,
-) -> *mut c_void {
-    assert!(!module_name.is_null());
-    assert!(!salts_buf.is_null());
-    assert!(!esalts_buf.is_null());
-    assert_eq!(salts_size as usize, mem::size_of::<salt_t>());
-    assert_eq!(esalts_size as usize, mem::size_of::<generic_io_t>());
-    let module_name = unsafe { string_from_ptr(module_name) };
-    let salts = unsafe { vec_from_raw_parts(salts_buf as *const salt_t, salts_cnt) };
-    let esalts = unsafe { vec_from_raw_parts(esalts_buf as *const generic_io_t, esalts_cnt) };
-
-    let bridge_parameter1 = unsafe { string_from_ptr(bridge_parameter1) };
-    let bridge_parameter2 = unsafe { string_from_ptr(bridge_parameter2) };
-    let bridge_parameter3 = unsafe { string_from_ptr(bridge_parameter3) };
-    let bridge_parameter4 = unsafe { string_from_ptr(bridge_parameter4) };
-
-    Box::into_raw(Box::new(ThreadContext {
-        module_name,
-        salts,
-        esalts,
-        bridge_parameter1,
-        bridge_parameter2,
-        bridge_parameter3,
-        bridge_parameter4,
-    })) as *mut c_void
+/// Binds the cost parameters the module stored in `salt_t` as decimal
+/// numbers (`$iter`, `$iter2`, `$repeats`, `$scrypt_n`, `$scrypt_r` and
+/// `$scrypt_p`) and the salt signature as `$sign`. Parameters the module left
+/// at zero are not bound, and neither are names the user already bound with
+/// an option, so a parameter never shadows a user variable.
+///
+/// Module 74000 only sets `salt_iter` (always to 1); the other parameters are
+/// there for modules that parse cost settings out of the hash line.
+pub(crate) fn bind_salt_params(ctx: &mut EvalContext, salt: &Salt) {
+    let params = [
+        ("iter", salt.salt_iter()),
+        ("iter2", salt.salt_iter2()),
+        ("repeats", salt.salt_repeats()),
+        ("scrypt_n", salt.scrypt_n()),
+        ("scrypt_r", salt.scrypt_r()),
+        ("scrypt_p", salt.scrypt_p()),
+    ];
+    for (name, value) in params {
+        if value != 0 && ctx.var(name).is_none() {
+            ctx.set_var(name, value.to_string());
+        }
+    }
+    let sign = salt.salt_sign();
+    let len = sign.iter().position(|&b| b == 0).unwrap_or(sign.len());
+    if len > 0 && ctx.var("sign").is_none() {
+        ctx.set_var("sign", &sign[..len]);
+    }
 }
 
-#[unsafe(no_mangle)]
-pub extern "C" fn drop_context(ctx: *mut c_void) {
-    assert!(!ctx.is_null());
-    unsafe {
-        drop(Box::from_raw(ctx as *mut ThreadContext));
+/// The distinct lengths of the stored hashes of a salt in ascending order,
+/// which the partial match modes shorten digests to. Unsalted hash lists put
+/// all digests under one salt, so this is done once per salt and not at all
+/// for exact matches.
+pub(crate) fn stored_lens(match_mode: MatchMode, salt: &Salt) -> Vec<usize> {
+    if match_mode == MatchMode::Exact {
+        return vec![];
     }
+    let mut lens: Vec<usize> = salt.digests().map(<[u8]>::len).collect();
+    lens.sort_unstable();
+    lens.dedup();
+    lens
 }
 
-#[unsafe(no_mangle)]
-pub extern "C" fn get_info(buf: *mut c_char, buf_size: c_int) -> c_int {
-    assert!(buf_size > 0);
-    let info = INFO.get().unwrap_or(&"");
-    let n = info.len().min(buf_size as usize);
-    unsafe {
-        ptr::copy_nonoverlapping(info.as_ptr(), buf as *mut u8, n);
-    }
-    n as c_int
+struct DynamicHash {
+    ast: Expr,
+    options: Options,
+    info: String,
 }
 
-#[unsafe(no_mangle)]
-pub extern "C" fn global_init(ctx: *mut bridge_context_t) -> bool {
-    assert!(!ctx.is_null());
-    let ctx = unsafe { &mut *ctx };
-    assert!(!ctx.dynlib_filename.is_null());
-
-    let dynlib_name = unsafe { string_from_ptr(ctx.dynlib_filename) };
-    let dynlib_name = Path::new(&dynlib_name)
-        .file_name()
-        .and_then(|x| x.to_str())
-        .unwrap_or_default();
-    let algorithm = unsafe { string_from_ptr(ctx.bridge_parameter2) };
-    match parse::parse(&algorithm) {
-        Ok(_) => {
-            let info = format!("Rust [{}] [{}]", dynlib_name, algorithm);
-            INFO.set(info.leak()).expect("global_init called twice");
-            true
+impl DynamicHash {
+    fn bind_salt(&self, salt: &Salt) -> Result<EvalContext, String> {
+        let mut eval_ctx = EvalContext::new();
+        for (name, value) in &self.options.vars {
+            eval_ctx.set_var(name, value);
         }
-        Err(err) => {
-            eprintln!("ERROR: failed to parse --bridge-parameter2 value: {}", err);
-            false
+        bind_salt_params(&mut eval_ctx, salt);
+        match &self.options.fields {
+            Some(fields) => fields.bind(&mut eval_ctx, salt.salt())?,
+            None => eval_ctx.set_salt(salt.salt()),
         }
+        Ok(eval_ctx)
     }
-}
-
-#[unsafe(no_mangle)]
-pub extern "C" fn global_term(_ctx: *mut bridge_context_t) {}
 
-#[unsafe(no_mangle)]
-pub extern "C" fn thread_init(ctx: *mut c_void) {
-    assert!(!ctx.is_null());
-    let ctx = unsafe { &mut *ctx.cast::<ThreadContext>() };
-    let ast = parse::parse(&ctx.bridge_parameter2).expect("invalid algorithm description");
-    AST.with(|c| c.set(ast).unwrap_or_default());
+    fn hash(
+        &self,
+        eval_ctx: &mut EvalContext,
+        password: &[u8],
+        stored_lens: &[usize],
+    ) -> Result<Vec<Output>, String> {
+        eval_ctx.set_var("p", password);
+        let hash = eval_ctx.eval(&self.ast)?;
+        Ok(self
+            .options
+            .match_mode
+            .apply_all(hash, stored_lens)
+            .into_iter()
+            .map(Output::Text)
+            .collect())
+    }
 }
 
-#[unsafe(no_mangle)]
-pub extern "C" fn thread_term(_ctx: *mut c_void) {}
+impl Bridge for DynamicHash {
+    type ThreadState = ();
+
+    const OPTIONS: &'static [OptionSpec] = OPTIONS;
 
-#[unsafe(no_mangle)]
-pub extern "C" fn kernel_loop(
-    ctx: *const c_void,
-    io: *mut generic_io_tmp_t,
-    pws_cnt: u64,
-    salt_id: c_int,
-    _is_self_test: bool,
-) -> bool {
-    assert!(!ctx.is_null());
-    assert!(!io.is_null());
-    let io = unsafe { slice::from_raw_parts_mut(io, pws_cnt as usize) };
+    // Any other name binds a variable, e.g. pepper=@/path/key.bin or
+    // pepper=env:PEPPER; parse_options rejects the ones the algorithm
+    // doesn't read.
+    const ACCEPTS_EXTRA_OPTIONS: bool = true;
 
-    let ctx = unsafe { &*ctx.cast::<ThreadContext>() };
+    fn global_init(ctx: &GlobalContext) -> Result<Self, String> {
+        let ast = parse_algorithm(&ctx.bridge_parameter2)
+            .map_err(|e| format!("failed to parse --bridge-parameter2 value: {}", e))?;
+        let options = parse_options(&ctx.options, &ast)?;
+        let info = ast.to_string();
+        Ok(Self { ast, options, info })
+    }
 
-    process_batch(ctx, io, salt_id as usize);
+    fn info(&self) -> Option<String> {
+        Some(self.info.clone())
+    }
 
-    true
-}
+    fn thread_init(&self, _ctx: &ThreadContext) -> Result<(), String> {
+        Ok(())
+    }
 
-fn process_batch(ctx: &ThreadContext, io: &mut [generic_io_tmp_t], salt_id: usize) {
-    let esalt = ctx.get_raw_esalt(salt_id);
-    let salt = unsafe {
-        slice::from_raw_parts(
-            esalt.salt_buf.as_ptr() as *const u8,
-            esalt.salt_len as usize,
+    fn calc_hash(
+        &self,
+        _state: &mut (),
+        password: &[u8],
+        salt: &Salt,
+    ) -> Result<Vec<Output>, String> {
+        let mut eval_ctx = self.bind_salt(salt)?;
+        self.hash(
+            &mut eval_ctx,
+            password,
+            &stored_lens(self.options.match_mode, salt),
         )
-    };
-
-    let mut eval_ctx = EvalContext::new();
-    eval_ctx.set_var("s", salt);
-    if salt.contains(&b'*') {
-        for (i, s) in salt.split(|&b| b == b'*').enumerate() {
-            eval_ctx.set_var(format!("s{}", i + 1), s);
-        }
     }
 
-    for in_out in io {
-        let pw = unsafe {
-            slice::from_raw_parts(in_out.pw_buf.as_ptr() as *const u8, in_out.pw_len as usize)
-        };
-        eval_ctx.set_var("p", pw);
-
-        let hash = AST
-            .with(|c| {
-                let ast = c.get().expect("no algorithm");
-                eval_ctx.eval(ast)
-            })
-            .unwrap_or_else(|e| {
-                LOG_ERROR_ONCE.call_once(|| eprintln!("ERROR: {}", e));
-                process::exit(-1);
-            });
-        assert!(hash.len() <= mem::size_of_val(&in_out.out_buf[0]));
-
-        in_out.out_cnt = 1;
-        unsafe {
-            ptr::copy_nonoverlapping(
-                hash.as_ptr(),
-                in_out.out_buf[0].as_mut_ptr() as *mut u8,
-                hash.len(),
-            );
-        }
-        in_out.out_len[0] = hash.len() as u32;
+    /// Binds the salt variables and collects the stored lengths once for the
+    /// whole batch.
+    fn process_batch(
+        &self,
+        _state: &mut (),
+        passwords: &[&[u8]],
+        salt: &Salt,
+    ) -> Result<Vec<Vec<Output>>, String> {
+        let mut eval_ctx = self.bind_salt(salt)?;
+        let stored_lens = stored_lens(self.options.match_mode, salt);
+        passwords
+            .iter()
+            .map(|password| self.hash(&mut eval_ctx, password, &stored_lens))
+            .collect()
     }
 }
+
+export_bridge!(DynamicHash);
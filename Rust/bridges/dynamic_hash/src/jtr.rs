@@ -0,0 +1,352 @@
+/**
+ * Author......: See docs/credits.txt
+ * License.....: MIT
+ */
+use std::fmt;
+
+use crate::{DataDecoder, Expr, OutputFormat};
+
+/// John the Ripper's built-in dynamic formats, written in its own expression
+/// syntax. Formats that rely on primitives dynamic_hash doesn't have (crypt
+/// style base64, padding, gost, whirlpool, ...) are not listed.
+const BUILTIN_FORMATS: &[(u32, &str)] = &[
+    (0, "md5($p)"),
+    (1, "md5($p.$s)"),
+    (2, "md5(md5($p))"),
+    (3, "md5(md5(md5($p)))"),
+    (4, "md5($s.$p)"),
+    (5, "md5($s.$p.$s)"),
+    (6, "md5(md5($p).$s)"),
+    (7, "md5(md5($p).$s)"),
+    (8, "md5(md5($s).$p)"),
+    (9, "md5($s.md5($p))"),
+    (10, "md5($s.md5($s.$p))"),
+    (11, "md5($s.md5($p.$s))"),
+    (12, "md5(md5($s).md5($p))"),
+    (13, "md5(md5($p).md5($s))"),
+    (14, "md5($s.md5($p).$s)"),
+    (15, "md5($u.md5($p).$s)"),
+    (16, "md5(md5(md5($p).$s).$s2)"),
+    (18, "md5($s.$c1.$p.$c2.$s),c1=Y,c2=\\xF7"),
+    (22, "md5(sha1($p))"),
+    (23, "sha1(md5($p))"),
+    (24, "sha1($p.$s)"),
+    (25, "sha1($s.$p)"),
+    (26, "sha1($p)"),
+    (29, "md5(utf16($p))"),
+    (30, "md4($p)"),
+    (31, "md4($s.$p)"),
+    (32, "md4($p.$s)"),
+    (33, "md4(utf16($p))"),
+    (34, "md5(md4($p))"),
+    (35, "sha1(uc($u).$c1.$p),c1=:"),
+    (36, "sha1($u.$c1.$p),c1=:"),
+    (37, "sha1(lc($u).$p)"),
+    (38, "sha1($s.sha1($s.sha1($p)))"),
+];
+
+/// The SHA-2 formats repeat the same nine schemes for every digest, starting
+/// at `dynamic_50` (sha224), 60 (sha256), 70 (sha384) and 80 (sha512).
+const SHA2_FORMATS: &[(u32, &str)] = &[
+    (50, "sha224"),
+    (60, "sha256"),
+    (70, "sha384"),
+    (80, "sha512"),
+];
+
+const SHA2_SCHEMES: &[&str] = &[
+    "H($p)",
+    "H($s.$p)",
+    "H($p.$s)",
+    "H(H($p))",
+    "H(H_raw($p))",
+    "H(H($p).$s)",
+    "H($s.H($p))",
+    "H(H($s).H($p))",
+    "H(H($p).H($p))",
+];
+
+const DIGESTS: &[&str] = &[
+    "md4", "md5", "sha1", "sha224", "sha256", "sha384", "sha512", "sha3_224", "sha3_256",
+    "sha3_384", "sha3_512",
+];
+
+/// Error returned when a JtR format can't be translated. Unsupported
+/// constructs are collected over the whole expression so that they can be
+/// reported at once.
+#[derive(Debug)]
+pub struct TranslateError {
+    pub msg: String,
+    pub unsupported: Vec<String>,
+}
+
+impl TranslateError {
+    fn new(msg: impl Into<String>) -> Self {
+        Self {
+            msg: msg.into(),
+            unsupported: vec![],
+        }
+    }
+}
+
+impl fmt::Display for TranslateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.msg)?;
+        if !self.unsupported.is_empty() {
+            write!(f, ": {}", self.unsupported.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for TranslateError {}
+
+/// Whether `s` names a JtR dynamic format rather than a dynamic_hash
+/// expression.
+pub fn is_jtr_format(s: &str) -> bool {
+    let s = s.trim();
+    s.starts_with("dynamic_") || s.starts_with("$dynamic_") || s.starts_with("dynamic=")
+}
+
+/// Translates a JtR dynamic format into an expression. Accepts built-in
+/// format numbers (`dynamic_12`, `$dynamic_12$`) and expression definitions
+/// (`dynamic=md5($s.md5($p)),c1=...`).
+///
+/// JtR's `$s2` refers to a second salt; when it is used, the salt is expected
+/// as `salt*salt2` and `$s`/`$s2` become `$s1`/`$s2`. The username `$u` has
+/// to be provided by a field schema with a field named `u`.
+pub fn translate(s: &str) -> Result<Expr, TranslateError> {
+    let s = s.trim();
+    if let Some(definition) = s.strip_prefix("dynamic=") {
+        return translate_expression(definition);
+    }
+    let number = s
+        .trim_start_matches('$')
+        .trim_end_matches('$')
+        .strip_prefix("dynamic_")
+        .and_then(|n| n.parse::<u32>().ok())
+        .ok_or_else(|| TranslateError::new(format!("Invalid JtR dynamic format '{}'", s)))?;
+    match builtin_format(number) {
+        Some(definition) => translate_expression(&definition),
+        None => Err(TranslateError::new(format!(
+            "dynamic_{} is not a supported built-in format",
+            number
+        ))),
+    }
+}
+
+fn builtin_format(number: u32) -> Option<String> {
+    if let Some((_, definition)) = BUILTIN_FORMATS.iter().find(|(n, _)| *n == number) {
+        return Some(definition.to_string());
+    }
+    SHA2_FORMATS.iter().find_map(|&(base, digest)| {
+        let scheme = SHA2_SCHEMES.get(number.checked_sub(base)? as usize)?;
+        Some(scheme.replace('H', digest))
+    })
+}
+
+fn unescape_constant(s: &str) -> Vec<u8> {
+    let bytes = s.as_bytes();
+    let mut out = vec![];
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = s
+            .get(i + 2..i + 4)
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        if let (b'\\', Some(b'x'), Some(byte)) = (bytes[i], bytes.get(i + 1), escaped) {
+            out.push(byte);
+            i += 4;
+            continue;
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    out
+}
+
+fn translate_expression(definition: &str) -> Result<Expr, TranslateError> {
+    // Options follow the expression after the first top-level comma.
+    let mut depth = 0;
+    let split = definition.char_indices().find(|&(_, c)| {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => return true,
+            _ => {}
+        }
+        false
+    });
+    let (expression, options) = match split {
+        Some((pos, _)) => (&definition[..pos], &definition[pos + 1..]),
+        None => (definition, ""),
+    };
+
+    let mut translator = Translator {
+        input: expression.chars().filter(|c| !c.is_whitespace()).collect(),
+        pos: 0,
+        constants: vec![None; 9],
+        has_salt2: expression.contains("$s2"),
+        unsupported: vec![],
+    };
+
+    for option in options.split(',').filter(|o| !o.is_empty()) {
+        let (key, value) = option.split_once('=').unwrap_or((option, ""));
+        let constant = key
+            .strip_prefix('c')
+            .and_then(|n| n.parse::<usize>().ok())
+            .filter(|n| (1..=9).contains(n));
+        match (key, constant) {
+            (_, Some(n)) => translator.constants[n - 1] = Some(unescape_constant(value)),
+            ("saltlen" | "debug" | "optimize", _) => {}
+            _ => translator.unsupported.push(format!("option '{}'", key)),
+        }
+    }
+
+    let expr = translator.parse_concat()?;
+    if translator.pos < translator.input.len() {
+        return Err(TranslateError::new(format!(
+            "Unexpected character '{}' at position {}",
+            translator.input[translator.pos], translator.pos
+        )));
+    }
+    if !translator.unsupported.is_empty() {
+        return Err(TranslateError {
+            msg: "Unsupported JtR constructs".to_string(),
+            unsupported: translator.unsupported,
+        });
+    }
+    Ok(expr)
+}
+
+struct Translator {
+    input: Vec<char>,
+    pos: usize,
+    constants: Vec<Option<Vec<u8>>>,
+    has_salt2: bool,
+    unsupported: Vec<String>,
+}
+
+impl Translator {
+    fn peek(&self) -> Option<char> {
+        self.input.get(self.pos).copied()
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), TranslateError> {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(TranslateError::new(format!(
+                "Expected '{}' at position {}",
+                c, self.pos
+            )))
+        }
+    }
+
+    fn ident(&mut self) -> String {
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|c| c.is_ascii_alphanumeric() || c == '_')
+        {
+            self.pos += 1;
+        }
+        self.input[start..self.pos].iter().collect()
+    }
+
+    fn parse_concat(&mut self) -> Result<Expr, TranslateError> {
+        let mut parts = vec![self.parse_term()?];
+        while self.peek() == Some('.') {
+            self.pos += 1;
+            parts.push(self.parse_term()?);
+        }
+        Ok(if parts.len() == 1 {
+            parts.pop().unwrap()
+        } else {
+            Expr::Concat(parts)
+        })
+    }
+
+    fn parse_term(&mut self) -> Result<Expr, TranslateError> {
+        match self.peek() {
+            Some('$') => {
+                self.pos += 1;
+                let name = self.ident();
+                Ok(self.variable(&name))
+            }
+            Some(c) if c.is_ascii_alphabetic() => {
+                let name = self.ident();
+                self.expect('(')?;
+                let arg = self.parse_concat()?;
+                self.expect(')')?;
+                Ok(self.function(&name, arg))
+            }
+            Some(c) => Err(TranslateError::new(format!(
+                "Unexpected character '{}' at position {}",
+                c, self.pos
+            ))),
+            None => Err(TranslateError::new("Unexpected end of input")),
+        }
+    }
+
+    fn variable(&mut self, name: &str) -> Expr {
+        let var = |name: &str| Expr::Var((name.to_string(), DataDecoder::None));
+        match name {
+            "p" => var("p"),
+            "s" if self.has_salt2 => var("s1"),
+            "s" => var("s"),
+            "s2" => var("s2"),
+            "u" => var("u"),
+            _ if name.len() == 2 && name.starts_with('c') => {
+                let n = name[1..].parse::<usize>().unwrap_or(0);
+                match n.checked_sub(1).and_then(|i| self.constants.get(i)) {
+                    Some(Some(value)) => Expr::Literal(value.clone()),
+                    _ => {
+                        self.unsupported
+                            .push(format!("undefined constant '${}'", name));
+                        Expr::Literal(vec![])
+                    }
+                }
+            }
+            _ => {
+                self.unsupported.push(format!("variable '${}'", name));
+                Expr::Literal(vec![])
+            }
+        }
+    }
+
+    fn function(&mut self, name: &str, arg: Expr) -> Expr {
+        let call = |name: &str, arg: Expr, output_format| Expr::Call {
+            name: name.to_string(),
+            arg: Box::new(arg),
+            params: None,
+            output_format,
+        };
+        match name {
+            "utf16" => return call("utf16le", arg, OutputFormat::Default),
+            "lc" => return call("lower", arg, OutputFormat::Default),
+            "uc" => return call("upper", arg, OutputFormat::Default),
+            _ => {}
+        }
+
+        // An uppercase name means uppercase hex output, a `_raw` suffix means
+        // binary output.
+        let uppercase = name.chars().any(|c| c.is_ascii_uppercase())
+            && !name.chars().any(|c| c.is_ascii_lowercase());
+        let lower = name.to_ascii_lowercase();
+        let (digest, output_format) = match lower.strip_suffix("_raw") {
+            Some(digest) => (digest, OutputFormat::Binary),
+            None => (lower.as_str(), OutputFormat::Default),
+        };
+        if !DIGESTS.contains(&digest) || (uppercase && output_format == OutputFormat::Binary) {
+            self.unsupported.push(format!("function '{}'", name));
+            return arg;
+        }
+        let expr = call(digest, arg, output_format);
+        if uppercase {
+            call("upper", expr, OutputFormat::Default)
+        } else {
+            expr
+        }
+    }
+}
@@ -2,14 +2,20 @@
  * Author......: See docs/credits.txt
  * License.....: MIT
  */
-mod eval;
+pub mod discover;
+mod display;
+pub mod eval;
+pub mod fields;
 mod interop;
-mod parse;
+pub mod jtr;
+pub mod parse;
+pub mod phc;
 
 #[cfg(test)]
 mod tests;
 
-#[derive(Copy, Clone, Default, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum OutputFormat {
     #[default]
     Default,
@@ -18,7 +24,8 @@ pub enum OutputFormat {
     Base64,
 }
 
-#[derive(Copy, Clone, Default, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum DataDecoder {
     #[default]
     None,
@@ -26,6 +33,8 @@ pub enum DataDecoder {
     B64Decode,
 }
 
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ExtraParams {
     Key(Box<Expr>),
     StartLength(u32, u32),
@@ -33,6 +42,8 @@ pub enum ExtraParams {
     RoundsSaltDklen(Box<Expr>, Box<Expr>, Box<Expr>),
 }
 
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Expr {
     Call {
         name: String,
@@ -43,5 +54,107 @@ pub enum Expr {
     Concat(Vec<Expr>),
     Var((String, DataDecoder)),
     Literal(Vec<u8>),
+    /// `file("path")`, with the contents read while parsing. It prints as the
+    /// path, so the contents, usually a secret key, stay out of banners.
+    File {
+        path: String,
+        data: Vec<u8>,
+    },
     Number(u32),
 }
+
+impl Expr {
+    /// Whether the expression reads the variable `name` anywhere.
+    pub fn references(&self, name: &str) -> bool {
+        match self {
+            Expr::Call { arg, params, .. } => {
+                arg.references(name)
+                    || match params {
+                        Some(ExtraParams::Key(key)) => key.references(name),
+                        Some(ExtraParams::StartLength(..)) | None => false,
+                        Some(ExtraParams::CostSalt(cost, salt)) => {
+                            cost.references(name) || salt.references(name)
+                        }
+                        Some(ExtraParams::RoundsSaltDklen(rounds, salt, dklen)) => {
+                            rounds.references(name)
+                                || salt.references(name)
+                                || dklen.references(name)
+                        }
+                    }
+            }
+            Expr::Concat(parts) => parts.iter().any(|part| part.references(name)),
+            Expr::Var((var, _)) => var == name,
+            Expr::Literal(_) | Expr::File { .. } | Expr::Number(_) => false,
+        }
+    }
+}
+
+/// How a computed digest is compared to the stored one. hashcat itself only
+/// compares for equality, so the partial modes shorten the digest to the
+/// length of the stored hash first.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum MatchMode {
+    #[default]
+    Exact,
+    /// The stored hash is the beginning of the digest.
+    Prefix,
+    /// The stored hash is the end of the digest.
+    Suffix,
+}
+
+impl MatchMode {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s {
+            "exact" => Ok(MatchMode::Exact),
+            "prefix" => Ok(MatchMode::Prefix),
+            "suffix" => Ok(MatchMode::Suffix),
+            _ => Err(format!("unsupported match mode '{}'", s)),
+        }
+    }
+
+    /// Shortens `digest` to `stored_len` bytes if the mode allows it. Digests
+    /// that are already short enough are left alone.
+    pub fn apply(&self, digest: &mut Vec<u8>, stored_len: usize) {
+        if digest.len() <= stored_len {
+            return;
+        }
+        match self {
+            MatchMode::Exact => {}
+            MatchMode::Prefix => digest.truncate(stored_len),
+            MatchMode::Suffix => {
+                digest.drain(..digest.len() - stored_len);
+            }
+        }
+    }
+
+    /// Shortens `digest` to each of the distinct stored hash lengths of a
+    /// salt, in ascending order, since its hash lines may differ in length.
+    /// Returns one digest per distinct result, or `digest` as is if there are
+    /// no stored hashes.
+    pub fn apply_all(&self, digest: Vec<u8>, stored_lens: &[usize]) -> Vec<Vec<u8>> {
+        if *self == MatchMode::Exact || stored_lens.is_empty() {
+            return vec![digest];
+        }
+        // Lengths beyond the digest all leave it alone and come last.
+        let mut digests: Vec<Vec<u8>> = stored_lens
+            .iter()
+            .map(|&len| {
+                let mut digest = digest.clone();
+                self.apply(&mut digest, len);
+                digest
+            })
+            .collect();
+        digests.dedup();
+        digests
+    }
+}
+
+/// Parses an algorithm description, which is either a dynamic_hash expression
+/// or a John the Ripper dynamic format (see `jtr::translate`).
+pub fn parse_algorithm(s: &str) -> Result<Expr, String> {
+    if jtr::is_jtr_format(s) {
+        jtr::translate(s).map_err(|e| e.to_string())
+    } else {
+        parse::parse(s).map_err(|e| e.to_string())
+    }
+}
@@ -3,7 +3,7 @@
  * License.....: MIT
  */
 use base64::{prelude::BASE64_STANDARD, Engine};
-use std::fmt;
+use std::{fmt, fs};
 
 use crate::{DataDecoder, Expr, ExtraParams, OutputFormat};
 
@@ -39,6 +39,28 @@ const SUPPORTED_ALGORITHMS: &[&str] = &[
     "bcrypt2y",
 ];
 
+/// Maps primitive aliases to the name that is stored in the tree and printed
+/// back.
+pub(crate) fn canonical_name(name: &str) -> &str {
+    match name {
+        "uc" => "upper",
+        "lc" => "lower",
+        "b64" => "base64",
+        "b64dec" => "b64decode",
+        "bcrypt2y" => "bcrypt",
+        other => other,
+    }
+}
+
+/// Same as `canonical_name`, for variables.
+pub(crate) fn canonical_var_name(name: &str) -> &str {
+    match name {
+        "pass" => "p",
+        "salt" => "s",
+        other => other,
+    }
+}
+
 #[derive(Debug)]
 pub struct ParseError {
     pub msg: String,
@@ -192,6 +214,16 @@ impl Parser {
             Some('"') => self.parse_string_literal(),
             Some('$') => self.parse_variable(),
             Some('0'..='9') if allow_numbers => Ok(Expr::Number(self.parse_number()?)),
+            Some(c) if c.is_ascii_lowercase() => {
+                let pos = self.pos;
+                if self.parse_ident_name()? != "file" {
+                    return Err(ParseError::new(
+                        format!("Only file(\"path\") may be used as the value of '{}'", name),
+                        pos,
+                    ));
+                }
+                self.parse_file()
+            }
             Some(c) => self.unexpected_char(c),
             None => Err(ParseError::new("Unexpected end of input", self.pos)),
         }?;
@@ -296,6 +328,10 @@ impl Parser {
     fn parse_call(&mut self) -> ParseResult<Expr> {
         let name = self.parse_ident_name()?;
 
+        if name == "file" {
+            return self.parse_file();
+        }
+
         if !SUPPORTED_ALGORITHMS.contains(&name.as_str())
             && !SUPPORTED_ALGORITHMS.contains(&name.strip_prefix("hmac_").unwrap_or_default())
             && !SUPPORTED_ALGORITHMS
@@ -314,8 +350,8 @@ impl Parser {
             (String::new(), OutputFormat::Default)
         };
 
-        if output_format != OutputFormat::Default {
-            if name.starts_with("bcrypt")
+        if output_format != OutputFormat::Default
+            && (name.starts_with("bcrypt")
                 || [
                     "hex",
                     "unhex",
@@ -330,13 +366,12 @@ impl Parser {
                     "cut",
                     "utf16le",
                 ]
-                .contains(&name.as_str())
-            {
-                return Err(ParseError::new(
-                    format!("Unsupported output format '{}' for '{}'", format_name, name),
-                    self.pos,
-                ));
-            }
+                .contains(&name.as_str()))
+        {
+            return Err(ParseError::new(
+                format!("Unsupported output format '{}' for '{}'", format_name, name),
+                self.pos,
+            ));
         }
 
         self.consume_char('(')?;
@@ -371,12 +406,29 @@ impl Parser {
 
         Ok(Expr::Call {
             arg: Box::new(arg),
-            name,
+            name: canonical_name(&name).to_string(),
             params,
             output_format,
         })
     }
 
+    /// `file("path")` stands for the contents of the file, read while
+    /// parsing. This keeps long or binary keys off the command line.
+    fn parse_file(&mut self) -> ParseResult<Expr> {
+        self.consume_char('(')?;
+        self.skip_ws();
+        let pos = self.pos;
+        let path = match self.parse_string_literal()? {
+            Expr::Literal(path) => path,
+            _ => unreachable!(),
+        };
+        self.consume_char(')')?;
+        let path = String::from_utf8(path).map_err(|e| ParseError::new(e, pos))?;
+        let data = fs::read(&path)
+            .map_err(|e| ParseError::new(format!("Failed to read '{}': {}", path, e), pos))?;
+        Ok(Expr::File { path, data })
+    }
+
     fn parse_ident_name(&mut self) -> ParseResult<String> {
         self.skip_ws();
         let mut s = String::new();
@@ -444,7 +496,7 @@ impl Parser {
             DataDecoder::None
         };
 
-        Ok(Expr::Var((name, decoder)))
+        Ok(Expr::Var((canonical_var_name(&name).to_string(), decoder)))
     }
 
     fn parse_hex_escape(&mut self, data: &mut Vec<u8>) -> Result<(), ParseError> {
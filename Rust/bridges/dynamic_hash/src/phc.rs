@@ -0,0 +1,131 @@
+/**
+ * Author......: See docs/credits.txt
+ * License.....: MIT
+ */
+use base64::{prelude::BASE64_STANDARD_NO_PAD, Engine};
+
+use crate::eval::EvalContext;
+
+/// A PHC string (`$argon2id$v=19$m=65536,t=3,p=4$<salt>$<hash>`) or modular
+/// crypt settings string (`$pbkdf2-sha256$29000$<salt>$`, `$6$rounds=5000$<salt>$`,
+/// `$2b$12$<salt><hash>`).
+#[derive(Debug, PartialEq, Eq)]
+pub struct PhcString {
+    pub alg: String,
+    pub rounds: Option<String>,
+    pub params: Vec<(String, String)>,
+    pub raw_salt: String,
+    pub salt: Vec<u8>,
+    pub hash: Option<String>,
+}
+
+/// Length of the salt of bcrypt, which is followed by the hash without a `$`.
+const BCRYPT_SALT_LEN: usize = 22;
+
+/// Decodes the base64 variants found in PHC and passlib strings: standard
+/// alphabet, optional padding and `.` in place of `+`.
+fn decode_b64(s: &str) -> Option<Vec<u8>> {
+    let s = s.trim_end_matches('=').replace('.', "+");
+    BASE64_STANDARD_NO_PAD.decode(s).ok()
+}
+
+impl PhcString {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let rest = s
+            .strip_prefix('$')
+            .ok_or_else(|| format!("PHC string '{}' doesn't start with '$'", s))?;
+        let mut segments = rest.split('$');
+        let alg = segments.next().unwrap_or_default().to_string();
+        if alg.is_empty() {
+            return Err(format!("PHC string '{}' has no algorithm", s));
+        }
+
+        // Only these schemes have a cost field without a name. Elsewhere a
+        // number is the salt, like in `$5$12345678$<hash>`.
+        let bcrypt = matches!(alg.as_str(), "2" | "2a" | "2b" | "2x" | "2y");
+        let positional_rounds = bcrypt || alg.starts_with("pbkdf2");
+
+        let mut rounds = None;
+        let mut params = vec![];
+        let mut raw_salt = None;
+        let mut hash = None;
+        for segment in segments {
+            if raw_salt.is_some() {
+                if !segment.is_empty() {
+                    hash = Some(segment.to_string());
+                }
+                break;
+            }
+            if segment.is_empty() {
+                continue;
+            }
+            if segment.contains('=') {
+                for param in segment.split(',') {
+                    let (key, value) = param
+                        .split_once('=')
+                        .ok_or_else(|| format!("Invalid PHC parameter '{}'", param))?;
+                    if key == "rounds" || key == "i" {
+                        rounds = Some(value.to_string());
+                    }
+                    params.push((key.to_string(), value.to_string()));
+                }
+            } else if positional_rounds
+                && rounds.is_none()
+                && segment.bytes().all(|b| b.is_ascii_digit())
+            {
+                rounds = Some(segment.to_string());
+            } else if let Some((salt, rest)) =
+                segment.split_at_checked(BCRYPT_SALT_LEN).filter(|_| bcrypt)
+            {
+                raw_salt = Some(salt.to_string());
+                if !rest.is_empty() {
+                    hash = Some(rest.to_string());
+                }
+                break;
+            } else {
+                raw_salt = Some(segment.to_string());
+            }
+        }
+        let raw_salt = raw_salt.ok_or_else(|| format!("PHC string '{}' has no salt", s))?;
+
+        // Classic crypt schemes ($1$, $5$, $6$, ...) and bcrypt use the salt
+        // characters as they are, PHC style schemes encode the salt in base64.
+        let salt = if alg.bytes().all(|b| b.is_ascii_digit()) || alg.len() == 1 || bcrypt {
+            raw_salt.as_bytes().to_vec()
+        } else {
+            decode_b64(&raw_salt)
+                .ok_or_else(|| format!("PHC salt '{}' is not valid base64", raw_salt))?
+        };
+
+        Ok(Self {
+            alg,
+            rounds,
+            params,
+            raw_salt,
+            salt,
+            hash,
+        })
+    }
+
+    /// Binds `$alg`, `$rounds`, `$s` (the decoded salt), `$rawsalt`, `$hash`
+    /// and one variable per parameter. The parameter `p` (argon2 parallelism)
+    /// is bound as `$parallelism` so that it doesn't hide the password.
+    pub fn bind(&self, ctx: &mut EvalContext) {
+        ctx.set_var("alg", &self.alg);
+        if let Some(rounds) = &self.rounds {
+            ctx.set_var("rounds", rounds);
+        }
+        for (key, value) in &self.params {
+            match key.as_str() {
+                "p" => ctx.set_var("parallelism", value),
+                "s" => {}
+                _ => ctx.set_var(key, value),
+            }
+        }
+        ctx.set_var("s", &self.salt);
+        ctx.set_var("rawsalt", &self.raw_salt);
+        if let Some(hash) = &self.hash {
+            ctx.set_var("hash", hash);
+        }
+    }
+}
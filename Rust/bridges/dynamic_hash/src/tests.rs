@@ -2,7 +2,22 @@
  * Author......: See docs/credits.txt
  * License.....: MIT
  */
-use crate::{eval::EvalContext, parse::parse};
+use hashcat_bridge::{
+    hashcat_sys::{generic_io_t, salt_t},
+    Options, Salt,
+};
+
+use crate::{
+    discover::{discover, Sample, SearchOptions},
+    eval::EvalContext,
+    fields::FieldSchema,
+    interop::{bind_salt_params, load_secret, parse_options, stored_lens, OPTIONS},
+    jtr::translate,
+    parse::parse,
+    parse_algorithm,
+    phc::PhcString,
+    MatchMode,
+};
 
 #[test]
 fn test_cmyic2024_hash() {
@@ -535,3 +550,505 @@ fn test_m12800() {
         "55b530f052a9af79a7ba9c466dddcb8b116f8babf6c3873a51a3898fb008e123"
     );
 }
+
+#[test]
+fn test_display_canonical() {
+    let algoritm = r#"bcrypt2y ( cost = 4, salt = $salt , hmac_sha256 : b64 ( key = "k", uc($pass) ) ) . "3A" : unhex"#;
+    let ast = parse(algoritm).unwrap();
+    assert_eq!(
+        ast.to_string(),
+        r#"bcrypt(cost=4,salt=$s,hmac_sha256:base64(key="k",upper($p))).":""#
+    );
+}
+
+#[test]
+fn test_display_aliases() {
+    let ast1 = parse("lc(b64(md5:raw($p))).b64dec($s).bcrypt2y(cost=4,salt=$s,$p)").unwrap();
+    let ast2 =
+        parse("lower(base64(md5:binary($p))).b64decode($s).bcrypt(cost=4,salt=$s,$p)").unwrap();
+    assert_eq!(ast1, ast2);
+    assert_eq!(ast1.to_string(), ast2.to_string());
+}
+
+#[test]
+fn test_display_escapes() {
+    let algoritm = r#""a\"b\\c\n\r\t\0\x01\xff€""#;
+    let ast = parse(algoritm).unwrap();
+    assert_eq!(ast.to_string(), r#""a\"b\\c\n\r\t\0\x01\xff\xe2\x82\xac""#);
+}
+
+#[test]
+fn test_display_round_trip() {
+    for algoritm in [
+        "bcrypt2b(cost=12,salt=$s,hmac_sha256:b64(key=$s,$p))",
+        r#"bcrypt(salt="zhVYF6gIwKr7Eaxd6pGQ8O",cost=$c,"pass")"#,
+        r#""*".upper(sha1(sha1:binary($p)))"#,
+        "upper(cut(0,16,md5($p)))",
+        "$p:unhex.$s:b64dec",
+        r#""cXdlcnR5":b64decode"#,
+        r#""md5:".$s1.":".$s2.":".pbkdf2_hmac_md5(rounds=$s1,salt=$s2:b64dec,dklen=$s3,$p)"#,
+        r#"pbkdf2_hmac_sha256:hex(dklen=32,rounds=100,salt=$s2:unhex,utf16le(upper(md4(utf16le($p)))))"#,
+    ] {
+        let ast = parse(algoritm).unwrap();
+        let canonical = ast.to_string();
+        let reparsed = parse(&canonical).unwrap();
+        assert_eq!(ast, reparsed, "{}", algoritm);
+        assert_eq!(canonical, reparsed.to_string());
+    }
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde_round_trip() {
+    let algoritm = r#""md5:".pbkdf2_hmac_md5:hex(rounds=1000,salt=$s:b64dec,dklen=$s3,$p)"#;
+    let ast = parse(algoritm).unwrap();
+    let json = serde_json::to_string(&ast).unwrap();
+    let deserialized: crate::Expr = serde_json::from_str(&json).unwrap();
+    assert_eq!(ast, deserialized);
+}
+
+fn sample(algoritm: &str, password: &str, salt: &str) -> Sample {
+    let mut ctx = EvalContext::new();
+    ctx.set_var("p", password);
+    ctx.set_salt(salt.as_bytes());
+    Sample {
+        password: password.into(),
+        salt: salt.into(),
+        hash: ctx.eval(&parse(algoritm).unwrap()).unwrap(),
+    }
+}
+
+fn discover_strings(samples: &[Sample], options: &SearchOptions) -> Vec<String> {
+    discover(samples, options)
+        .iter()
+        .map(|e| e.to_string())
+        .collect()
+}
+
+#[test]
+fn test_discover_salted() {
+    let algoritm = "md5(md5($p).$s)";
+    let samples = [
+        sample(algoritm, "hashcat", "1234"),
+        sample(algoritm, "qwerty", "abcd"),
+    ];
+    let found = discover_strings(&samples, &SearchOptions::default());
+    assert_eq!(found, ["md5(md5($p).$s)"]);
+}
+
+#[test]
+fn test_discover_encodings() {
+    let samples = [sample(r#"upper(sha1(sha1:bin($p)))"#, "qwerty", "")];
+    let found = discover_strings(&samples, &SearchOptions::default());
+    assert!(found.contains(&"upper(sha1(sha1:bin($p)))".to_string()));
+
+    let samples = [
+        sample("hmac_sha256:b64(key=$s,$p)", "hashcat", "k1"),
+        sample("hmac_sha256:b64(key=$s,$p)", "hashcat", "k2"),
+    ];
+    let found = discover_strings(&samples, &SearchOptions::default());
+    assert_eq!(found, ["hmac_sha256:base64(key=$s,$p)"]);
+}
+
+#[test]
+fn test_discover_iterations() {
+    let samples = [sample("sha1(sha1(sha1(sha1($p))))", "hashcat", "salt")];
+    let options = SearchOptions {
+        max_depth: 1,
+        max_iterations: 4,
+    };
+    let found = discover_strings(&samples, &options);
+    assert_eq!(found, ["sha1(sha1(sha1(sha1($p))))"]);
+    assert!(discover_strings(&samples, &SearchOptions::default()).is_empty());
+}
+
+#[test]
+fn test_discover_utf16le() {
+    let samples = [sample("md4(utf16le($p))", "hashcat", "")];
+    let options = SearchOptions {
+        max_depth: 1,
+        max_iterations: 1,
+    };
+    assert_eq!(discover_strings(&samples, &options), ["md4(utf16le($p))"]);
+}
+
+#[test]
+fn test_jtr_builtin_formats() {
+    for (jtr, expected) in [
+        ("dynamic_0", "md5($p)"),
+        ("$dynamic_12$", "md5(md5($s).md5($p))"),
+        ("dynamic_16", "md5(md5(md5($p).$s1).$s2)"),
+        ("dynamic_18", r#"md5($s."Y".$p."\xf7".$s)"#),
+        ("dynamic_33", "md4(utf16le($p))"),
+        ("dynamic_54", "sha224(sha224:bin($p))"),
+        ("dynamic_67", "sha256(sha256($s).sha256($p))"),
+        ("dynamic_88", "sha512(sha512($p).sha512($p))"),
+    ] {
+        assert_eq!(translate(jtr).unwrap(), parse(expected).unwrap(), "{}", jtr);
+    }
+    assert!(translate("dynamic_17").is_err());
+    assert!(translate("dynamic_89").is_err());
+    assert!(translate("dynamic_x").is_err());
+}
+
+#[test]
+fn test_jtr_expressions() {
+    for (jtr, expected) in [
+        ("dynamic=md5($s.md5($p))", "md5($s.md5($p))"),
+        ("dynamic=MD5(sha1_raw($p))", "upper(md5(sha1:bin($p)))"),
+        (
+            "dynamic=sha256($s.$c1.$p),c1=\\x00--,saltlen=8",
+            r#"sha256($s."\0--".$p)"#,
+        ),
+        ("dynamic=sha1(lc(utf16($p)))", "sha1(lower(utf16le($p)))"),
+    ] {
+        assert_eq!(
+            parse_algorithm(jtr).unwrap(),
+            parse(expected).unwrap(),
+            "{}",
+            jtr
+        );
+    }
+}
+
+#[test]
+fn test_jtr_unsupported() {
+    let err = translate("dynamic=md5(whirlpool($p).$x.md5_64c($c3)),rdp").unwrap_err();
+    assert_eq!(
+        err.unsupported,
+        [
+            "option 'rdp'",
+            "function 'whirlpool'",
+            "variable '$x'",
+            "undefined constant '$c3'",
+            "function 'md5_64c'"
+        ]
+    );
+    let err = translate("dynamic=md5($p").unwrap_err();
+    assert!(err.unsupported.is_empty());
+}
+
+#[test]
+fn test_jtr_hash() {
+    let ast = parse_algorithm("dynamic_6").unwrap();
+    let mut ctx = EvalContext::new();
+    ctx.set_var("p", "hashcat");
+    ctx.set_salt(b"salt");
+    let hash = String::from_utf8(ctx.eval(&ast).unwrap()).unwrap();
+    let mut ctx = EvalContext::new();
+    ctx.set_var("p", "hashcat");
+    ctx.set_salt(b"salt");
+    let expected = ctx.eval(&parse("md5(md5($p).$s)").unwrap()).unwrap();
+    assert_eq!(hash.as_bytes(), expected);
+}
+
+#[test]
+fn test_fields() {
+    let fields = FieldSchema::parse("user:salt(hex):iter").unwrap();
+    let ast =
+        parse(r#"pbkdf2_hmac_sha256:hex(rounds=$iter,salt=$salt,dklen=16,$user.":".$p)"#).unwrap();
+    let mut ctx = EvalContext::new();
+    ctx.set_var("p", "hashcat");
+    fields.bind(&mut ctx, b"admin:73616c74:1000").unwrap();
+    assert_eq!(ctx.var("user").unwrap(), b"admin");
+    assert_eq!(ctx.var("s").unwrap(), b"salt");
+    assert_eq!(ctx.var("iter").unwrap(), b"1000");
+    let hash = ctx.eval(&ast).unwrap();
+
+    let mut ctx = EvalContext::new();
+    ctx.set_var("p", "hashcat");
+    let expected = ctx
+        .eval(
+            &parse(r#"pbkdf2_hmac_sha256:hex(rounds=1000,salt="salt",dklen=16,"admin:".$p)"#)
+                .unwrap(),
+        )
+        .unwrap();
+    assert_eq!(hash, expected);
+}
+
+#[test]
+fn test_fields_last_takes_rest() {
+    let fields = FieldSchema::parse("u*rest(b64)").unwrap();
+    let mut ctx = EvalContext::new();
+    fields.bind(&mut ctx, b"bob*Kio=").unwrap();
+    assert_eq!(ctx.var("u").unwrap(), b"bob");
+    assert_eq!(ctx.var("rest").unwrap(), b"**");
+    assert_eq!(ctx.var("s").unwrap(), b"bob*Kio=");
+
+    let fields = FieldSchema::parse("a|b|c").unwrap();
+    assert!(fields.bind(&mut ctx, b"x|y").is_err());
+}
+
+#[test]
+fn test_fields_invalid() {
+    assert!(FieldSchema::parse("user:pass").is_err());
+    assert!(FieldSchema::parse("user:user").is_err());
+    assert!(FieldSchema::parse("user:salt(rot13)").is_err());
+    assert!(FieldSchema::parse("user;salt").is_err());
+    assert!(FieldSchema::parse("user::salt").is_err());
+}
+
+#[test]
+fn test_fields_jtr_username() {
+    let ast = parse_algorithm("dynamic_37").unwrap();
+    let fields = FieldSchema::parse("u").unwrap();
+    let mut ctx = EvalContext::new();
+    ctx.set_var("p", "hashcat");
+    fields.bind(&mut ctx, b"Admin").unwrap();
+    let hash = ctx.eval(&ast).unwrap();
+    let expected = ctx.eval(&parse(r#"sha1("admin".$p)"#).unwrap()).unwrap();
+    assert_eq!(hash, expected);
+}
+
+#[test]
+fn test_phc_parse() {
+    let phc = PhcString::parse("$argon2id$v=19$m=65536,t=3,p=4$c2FsdHNhbHQ$aGFzaA").unwrap();
+    assert_eq!(phc.alg, "argon2id");
+    assert_eq!(phc.rounds, None);
+    assert_eq!(
+        phc.params,
+        [
+            ("v".to_string(), "19".to_string()),
+            ("m".to_string(), "65536".to_string()),
+            ("t".to_string(), "3".to_string()),
+            ("p".to_string(), "4".to_string())
+        ]
+    );
+    assert_eq!(phc.salt, b"saltsalt");
+    assert_eq!(phc.hash.as_deref(), Some("aGFzaA"));
+
+    let phc = PhcString::parse("$pbkdf2-sha256$29000$N2bsXWtNKeU8ZyzF2Dvnnw$").unwrap();
+    assert_eq!(phc.alg, "pbkdf2-sha256");
+    assert_eq!(phc.rounds.as_deref(), Some("29000"));
+    assert_eq!(phc.salt.len(), 16);
+    assert_eq!(phc.hash, None);
+
+    let phc = PhcString::parse("$6$rounds=5000$saltstring$").unwrap();
+    assert_eq!(phc.rounds.as_deref(), Some("5000"));
+    assert_eq!(phc.salt, b"saltstring");
+
+    let phc = PhcString::parse("$pbkdf2-sha1$1000$a.b/cw$").unwrap();
+    assert_eq!(phc.salt, [0x6b, 0xe6, 0xff, 0x73]);
+
+    // A number is only the cost where the scheme puts one.
+    let phc = PhcString::parse("$5$12345678$hash").unwrap();
+    assert_eq!(phc.rounds, None);
+    assert_eq!(phc.salt, b"12345678");
+    assert_eq!(phc.hash.as_deref(), Some("hash"));
+
+    let phc =
+        PhcString::parse("$2b$12$iyvX.kDndyq/YiUvv7J.NePApguNMJG65lr2k7H0A7Y3d7LLc1tOS").unwrap();
+    assert_eq!(phc.alg, "2b");
+    assert_eq!(phc.rounds.as_deref(), Some("12"));
+    assert_eq!(phc.salt, b"iyvX.kDndyq/YiUvv7J.Ne");
+    assert_eq!(phc.hash.as_deref(), Some("PApguNMJG65lr2k7H0A7Y3d7LLc1tOS"));
+    let phc = PhcString::parse("$2y$04$zhVYF6gIwKr7Eaxd6pGQ8O").unwrap();
+    assert_eq!(phc.salt, b"zhVYF6gIwKr7Eaxd6pGQ8O");
+    assert_eq!(phc.hash, None);
+
+    assert!(PhcString::parse("pbkdf2$1000$salt$").is_err());
+    assert!(PhcString::parse("$pbkdf2-sha1$1000$").is_err());
+    assert!(PhcString::parse("$argon2id$v=19$m=65536,t=3,p=4$!!!$").is_err());
+}
+
+#[test]
+fn test_phc_fields() {
+    // rows with different iteration counts share one recipe
+    let ast = parse("pbkdf2_hmac_sha256:b64(rounds=$rounds,salt=$s,dklen=32,$p)").unwrap();
+    let fields = FieldSchema::parse("settings(phc)").unwrap();
+    for rounds in [1000, 2000] {
+        let mut ctx = EvalContext::new();
+        ctx.set_var("p", "hashcat");
+        fields
+            .bind(
+                &mut ctx,
+                format!("$pbkdf2-sha256${}$c2FsdA$", rounds).as_bytes(),
+            )
+            .unwrap();
+        assert_eq!(ctx.var("alg").unwrap(), b"pbkdf2-sha256");
+        assert_eq!(ctx.var("p").unwrap(), b"hashcat");
+        let hash = ctx.eval(&ast).unwrap();
+
+        let mut ctx = EvalContext::new();
+        ctx.set_var("p", "hashcat");
+        let expected = ctx
+            .eval(
+                &parse(&format!(
+                    r#"pbkdf2_hmac_sha256:b64(rounds={},salt="salt",dklen=32,$p)"#,
+                    rounds
+                ))
+                .unwrap(),
+            )
+            .unwrap();
+        assert_eq!(hash, expected);
+    }
+
+    let fields = FieldSchema::parse("user*settings(phc)").unwrap();
+    let mut ctx = EvalContext::new();
+    ctx.set_var("p", "hashcat");
+    fields
+        .bind(&mut ctx, b"bob*$argon2id$v=19$m=65536,t=3,p=4$c2FsdA$")
+        .unwrap();
+    assert_eq!(ctx.var("m").unwrap(), b"65536");
+    assert_eq!(ctx.var("t").unwrap(), b"3");
+    assert_eq!(ctx.var("parallelism").unwrap(), b"4");
+    assert_eq!(ctx.var("p").unwrap(), b"hashcat");
+    assert_eq!(ctx.var("salt").unwrap(), b"salt");
+}
+
+#[test]
+fn test_match_mode() {
+    let mut ctx = EvalContext::new();
+    ctx.set_var("p", "hashcat");
+    let digest = ctx.eval(&parse("md5($p)").unwrap()).unwrap();
+    assert_eq!(digest, b"8743b52063cd84097a65d1633f5c74f5");
+
+    let mut prefix = digest.clone();
+    MatchMode::Prefix.apply(&mut prefix, 16);
+    assert_eq!(prefix, b"8743b52063cd8409");
+
+    let mut suffix = digest.clone();
+    MatchMode::Suffix.apply(&mut suffix, 16);
+    assert_eq!(suffix, b"7a65d1633f5c74f5");
+
+    let mut exact = digest.clone();
+    MatchMode::Exact.apply(&mut exact, 16);
+    assert_eq!(exact, digest);
+
+    // Longer stored hashes never match, the digest is kept as is.
+    let mut longer = digest.clone();
+    MatchMode::Prefix.apply(&mut longer, 64);
+    assert_eq!(longer, digest);
+
+    // Hash lines of different lengths under one salt each get a digest.
+    assert_eq!(
+        MatchMode::Prefix.apply_all(digest.clone(), &[8, 16, 32, 64]),
+        [&b"8743b520"[..], b"8743b52063cd8409", &digest[..]]
+    );
+    assert_eq!(
+        MatchMode::Suffix.apply_all(digest.clone(), &[40, 64]),
+        [&digest[..]]
+    );
+    assert_eq!(
+        MatchMode::Exact.apply_all(digest.clone(), &[8]),
+        [&digest[..]]
+    );
+    assert_eq!(
+        MatchMode::Prefix.apply_all(digest.clone(), &[]),
+        [&digest[..]]
+    );
+
+    // The lengths are collected once per salt, and only where they matter.
+    let raw: salt_t = unsafe { std::mem::zeroed() };
+    let esalts = [16, 8, 16].map(|len| {
+        let mut esalt: generic_io_t = unsafe { std::mem::zeroed() };
+        esalt.hash_len = len;
+        esalt
+    });
+    let salt = Salt {
+        id: 0,
+        is_selftest: false,
+        raw: &raw,
+        esalts: &esalts,
+    };
+    assert_eq!(stored_lens(MatchMode::Prefix, &salt), [8, 16]);
+    assert!(stored_lens(MatchMode::Exact, &salt).is_empty());
+
+    assert_eq!(MatchMode::parse("suffix").unwrap(), MatchMode::Suffix);
+    assert!(MatchMode::parse("middle").is_err());
+}
+
+#[test]
+fn test_salt_params() {
+    let mut raw: salt_t = unsafe { std::mem::zeroed() };
+    raw.salt_iter = 1000;
+    raw.scrypt_N = 1024;
+    raw.scrypt_r = 8;
+    raw.scrypt_p = 1;
+    let salt = Salt {
+        id: 0,
+        is_selftest: false,
+        raw: &raw,
+        esalts: &[],
+    };
+
+    let mut ctx = EvalContext::new();
+    bind_salt_params(&mut ctx, &salt);
+    assert_eq!(ctx.var("iter").unwrap(), b"1000");
+    assert_eq!(ctx.var("scrypt_n").unwrap(), b"1024");
+    assert_eq!(ctx.var("scrypt_r").unwrap(), b"8");
+    assert_eq!(ctx.var("scrypt_p").unwrap(), b"1");
+    assert_eq!(ctx.var("iter2"), None);
+    assert_eq!(ctx.var("sign"), None);
+
+    ctx.set_var("p", "hashcat");
+    ctx.set_var("s", "salt");
+    let hash = ctx
+        .eval(&parse("pbkdf2_hmac_sha256(rounds=$iter,salt=$s,dklen=32,$p)").unwrap())
+        .unwrap();
+    let expected = ctx
+        .eval(&parse(r#"pbkdf2_hmac_sha256(rounds=1000,salt="salt",dklen=32,$p)"#).unwrap())
+        .unwrap();
+    assert_eq!(hash, expected);
+
+    // A user variable of the same name wins over the salt parameter.
+    let mut ctx = EvalContext::new();
+    ctx.set_var("iter", "5000");
+    bind_salt_params(&mut ctx, &salt);
+    assert_eq!(ctx.var("iter").unwrap(), b"5000");
+    assert_eq!(ctx.var("scrypt_n").unwrap(), b"1024");
+}
+
+#[test]
+fn test_file_literal() {
+    let path = std::env::temp_dir().join(format!("dynamic_hash_key_{}.bin", std::process::id()));
+    std::fs::write(&path, b"\x00secret\xff").unwrap();
+    let path = path.to_str().unwrap().replace('\\', "/");
+
+    let ast = parse(&format!(r#"hmac_sha256(key=file("{}"),$p)"#, path)).unwrap();
+    let expected = parse(r#"hmac_sha256(key="\x00secret\xff",$p)"#).unwrap();
+    let mut ctx = EvalContext::new();
+    ctx.set_var("p", "hashcat");
+    assert_eq!(ctx.eval(&ast).unwrap(), ctx.eval(&expected).unwrap());
+
+    // The canonical form, shown in the banner, names the file instead of
+    // printing the key, also with whitespace before the parenthesis.
+    let canonical = format!(r#"hmac_sha256(key=file("{}"),$p)"#, path);
+    assert_eq!(ast.to_string(), canonical);
+    let spaced = parse(&format!(r#"hmac_sha256(key=file ( "{}" ), $p)"#, path)).unwrap();
+    assert_eq!(spaced.to_string(), canonical);
+    assert!(!spaced.to_string().contains("secret"));
+    assert_eq!(parse(&canonical).unwrap(), ast);
+
+    // Named parameters take no other calls.
+    assert!(parse(r#"hmac_sha256(key=md5("k"),$p)"#).is_err());
+    assert!(parse(r#"pbkdf2_hmac_sha256(rounds=1000,salt=lower($s),dklen=32,$p)"#).is_err());
+
+    assert_eq!(
+        load_secret(&format!("@{}", path)).unwrap(),
+        b"\x00secret\xff"
+    );
+    assert_eq!(load_secret("plain").unwrap(), b"plain");
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(parse(r#"md5(file("/nonexistent/key.bin"))"#).is_err());
+    assert!(load_secret("@/nonexistent/key.bin").is_err());
+    assert!(load_secret("env:DYNAMIC_HASH_UNSET_VARIABLE").is_err());
+}
+
+#[test]
+fn test_variable_options() {
+    let ast = parse_algorithm("sha256($p.$pepper)").unwrap();
+    let parse = |value: &str| {
+        let options = Options::parse(&[("--bridge-parameter3", value)], OPTIONS, true).unwrap();
+        parse_options(&options, &ast)
+    };
+
+    assert!(parse("pepper=x;match=prefix").is_ok());
+    assert_eq!(
+        parse("mach=prefix").unwrap_err(),
+        "unknown option 'mach', and the algorithm has no variable $mach"
+    );
+    assert!(parse("pepper=x;peper=y").is_err());
+    assert!(parse("pass=x").is_err());
+}
@@ -2,7 +2,7 @@
  * Author......: See docs/credits.txt
  * License.....: MIT
  */
-use crate::interop::ThreadContext;
+use hashcat_bridge::{OptionSpec, Output, ThreadContext};
 use sha2::{Digest, Sha256};
 
 // Trailing zeroes are necessary.
@@ -12,8 +12,30 @@ pub static ST_HASH: &[u8] =
 #[unsafe(no_mangle)]
 pub static ST_PASS: &[u8] = b"hashcat\0";
 
-pub(crate) fn calc_hash(password: &[u8], salt: &[u8]) -> Vec<String> {
-    let mut sha256 = Sha256::new();
+// Options accepted as `key=value;key=value` in --bridge-parameter3 and
+// --bridge-parameter4, e.g. `OptionSpec { name: "cost", help: "work factor" }`.
+// Unknown options are rejected at startup; the values are available as
+// `ctx.options` in `thread_init`.
+pub(crate) const OPTIONS: &[OptionSpec] = &[];
+
+// Per-thread state, created by `thread_init` and passed to every call. Use it
+// for buffers, lookup tables or parsed parameters that are costly to set up.
+#[derive(Default)]
+pub(crate) struct ThreadState {
+    sha256: Sha256,
+}
+
+// `salt_id` identifies the salt across calls, e.g. to cache per-salt data in
+// the state; self-test salts are numbered separately.
+#[allow(unused_variables)]
+pub(crate) fn calc_hash(
+    state: &mut ThreadState,
+    password: &[u8],
+    salt: &[u8],
+    salt_id: usize,
+    is_selftest: bool,
+) -> Vec<Output> {
+    let sha256 = &mut state.sha256;
     sha256.update(salt);
     sha256.update(password);
     let mut hash = sha256.finalize_reset();
@@ -22,14 +44,49 @@ pub(crate) fn calc_hash(password: &[u8], salt: &[u8]) -> Vec<String> {
         hash = sha256.finalize_reset();
     }
 
-    vec![hex::encode(hash)]
+    vec![Output::Binary(hash.to_vec())]
 }
 
+// Optional batch variant of `calc_hash`, for implementations that share work
+// between candidates or hash several of them at once. Return one entry per
+// password, or `None` to fall back to calling `calc_hash` for each of them.
 #[allow(unused_variables)]
-pub(crate) fn thread_init(ctx: &mut ThreadContext) {}
+pub(crate) fn calc_batch(
+    state: &mut ThreadState,
+    passwords: &[&[u8]],
+    salt: &[u8],
+    salt_id: usize,
+    is_selftest: bool,
+) -> Option<Vec<Vec<Output>>> {
+    None
+}
+
+// Set to true to check candidates with `verify` instead of `calc_hash`.
+pub(crate) const USE_VERIFY: bool = false;
+
+// Alternative to `calc_hash` for schemes that need the stored hash to decide
+// whether a password matches, e.g. decrypting a container and checking the
+// padding. A match is reported to hashcat by echoing the stored hash.
+pub(crate) fn verify(
+    state: &mut ThreadState,
+    password: &[u8],
+    salt: &[u8],
+    stored_hash: &[u8],
+    salt_id: usize,
+    is_selftest: bool,
+) -> bool {
+    calc_hash(state, password, salt, salt_id, is_selftest)
+        .into_iter()
+        .any(|hash| hash.encode(stored_hash) == stored_hash)
+}
 
 #[allow(unused_variables)]
-pub(crate) fn thread_term(ctx: &mut ThreadContext) {}
+pub(crate) fn thread_init(ctx: &ThreadContext) -> ThreadState {
+    ThreadState::default()
+}
+
+#[allow(unused_variables)]
+pub(crate) fn thread_term(ctx: &ThreadContext, state: &mut ThreadState) {}
 
 #[cfg(test)]
 mod tests {
@@ -39,11 +96,41 @@ mod tests {
     fn test_calc_hash() {
         let password = b"hashcat";
         let salt = b"9348746780603343";
-        let hash = calc_hash(password, salt);
+        let mut state = ThreadState::default();
+        let hash = calc_hash(&mut state, password, salt, 0, false);
+        assert_eq!(hash.len(), 1);
+        assert_eq!(
+            hash[0],
+            Output::Binary(
+                hex::decode("33522b0fd9812aa68586f66dba7c17a8ce64344137f9c7d8b11f32a6921c22de")
+                    .unwrap()
+            )
+        );
+        // The hasher in the state is reused.
+        let hash = calc_hash(&mut state, password, salt, 0, false);
         assert_eq!(hash.len(), 1);
         assert_eq!(
             hash[0],
-            "33522b0fd9812aa68586f66dba7c17a8ce64344137f9c7d8b11f32a6921c22de"
+            Output::Binary(
+                hex::decode("33522b0fd9812aa68586f66dba7c17a8ce64344137f9c7d8b11f32a6921c22de")
+                    .unwrap()
+            )
         );
     }
+
+    #[test]
+    fn test_verify() {
+        let salt = b"9348746780603343";
+        let stored_hash = b"33522b0fd9812aa68586f66dba7c17a8ce64344137f9c7d8b11f32a6921c22de";
+        let mut state = ThreadState::default();
+        assert!(verify(&mut state, b"hashcat", salt, stored_hash, 0, false));
+        assert!(!verify(
+            &mut state,
+            b"hashcat!",
+            salt,
+            stored_hash,
+            0,
+            false
+        ));
+    }
 }
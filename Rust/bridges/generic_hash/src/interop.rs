@@ -2,225 +2,167 @@
  * Author......: See docs/credits.txt
  * License.....: MIT
  */
-use std::{
-    ffi::{c_char, c_int, c_void, CStr},
-    mem,
-    path::Path,
-    ptr, slice,
-    sync::OnceLock,
+use hashcat_bridge::{
+    export_bridge, hashcat_sys::generic_io_tmp_t, Bridge, GlobalContext, OptionSpec, Output, Salt,
+    ThreadContext,
 };
 
-use hashcat_sys::{bridge_context_t, generic_io_t, generic_io_tmp_t, salt_t};
-
 use crate::generic_hash;
 
-static INFO: OnceLock<&'static str> = OnceLock::new();
-
-#[repr(C)]
-pub(crate) struct ThreadContext {
-    pub module_name: String,
-
-    pub salts: Vec<salt_t>,
-    pub esalts: Vec<generic_io_t>,
-    pub st_salts: Vec<salt_t>,
-    pub st_esalts: Vec<generic_io_t>,
-
-    pub bridge_parameter1: String,
-    pub bridge_parameter2: String,
-    pub bridge_parameter3: String,
-    pub bridge_parameter4: String,
+struct GenericHash;
+
+/// Checks the password against every hash line of the salt. Hashcat compares
+/// each output with the stored hashes, so echoing the ones that verify
+/// reports them as cracked. A candidate can only carry
+/// [`generic_io_tmp_t::MAX_OUTPUTS`] outputs, so checking stops at that many
+/// matches instead of failing the whole candidate.
+fn verify_digests(
+    state: &mut generic_hash::ThreadState,
+    password: &[u8],
+    salt: &Salt,
+) -> Vec<Output> {
+    salt.digests()
+        .filter(|stored_hash| {
+            generic_hash::verify(
+                state,
+                password,
+                salt.salt(),
+                stored_hash,
+                salt.id,
+                salt.is_selftest,
+            )
+        })
+        .take(generic_io_tmp_t::MAX_OUTPUTS)
+        .map(|stored_hash| Output::Text(stored_hash.to_vec()))
+        .collect()
 }
 
-impl ThreadContext {
-    fn get_raw_esalt(&self, salt_id: usize, is_selftest: bool) -> &generic_io_t {
-        if is_selftest {
-            &self.st_esalts[salt_id]
-        } else {
-            &self.esalts[salt_id]
-        }
-    }
-}
+impl Bridge for GenericHash {
+    type ThreadState = generic_hash::ThreadState;
 
-unsafe fn vec_from_raw_parts<T: Clone>(data: *const T, length: c_int) -> Vec<T> {
-    Vec::from(unsafe { slice::from_raw_parts(data, length as usize) })
-}
+    const OPTIONS: &'static [OptionSpec] = generic_hash::OPTIONS;
 
-unsafe fn string_from_ptr(ptr: *const c_char) -> String {
-    if ptr.is_null() {
-        String::new()
-    } else {
-        unsafe { CStr::from_ptr(ptr).to_str().unwrap_or_default().to_string() }
+    fn global_init(_ctx: &GlobalContext) -> Result<Self, String> {
+        Ok(GenericHash)
     }
-}
-
-#[unsafe(no_mangle)]
-pub extern "C" fn new_context(
-    module_name: *const c_char,
-
-    salts_cnt: c_int,
-    salts_size: c_int,
-    salts_buf: *const c_char,
-
-    esalts_cnt: c_int,
-    esalts_size: c_int,
-    esalts_buf: *const c_char,
-
-    st_salts_cnt: c_int,
-    st_salts_size: c_int,
-    st_salts_buf: *const c_char,
-
-    st_esalts_cnt: c_int,
-    st_esalts_size: c_int,
-    st_esalts_buf: *const c_char,
-
-    bridge_parameter1: *const c_char,
-    bridge_parameter2: *const c_char,
-    bridge_parameter3: *const c_char,
-    bridge_parameter4: *const c_char,
-) -> *mut c_void {
-    assert!(!module_name.is_null());
-    assert!(!salts_buf.is_null());
-    assert!(!esalts_buf.is_null());
-    assert!(!st_salts_buf.is_null());
-    assert!(!st_esalts_buf.is_null());
-    assert_eq!(salts_size as usize, mem::size_of::<salt_t>());
-    assert_eq!(st_salts_size as usize, mem::size_of::<salt_t>());
-    assert_eq!(esalts_size as usize, mem::size_of::<generic_io_t>());
-    assert_eq!(st_esalts_size as usize, mem::size_of::<generic_io_t>());
-    let module_name = unsafe { string_from_ptr(module_name) };
-    let salts = unsafe { vec_from_raw_parts(salts_buf as *const salt_t, salts_cnt) };
-    let esalts = unsafe { vec_from_raw_parts(esalts_buf as *const generic_io_t, esalts_cnt) };
-    let st_salts = unsafe { vec_from_raw_parts(st_salts_buf as *const salt_t, st_salts_cnt) };
-    let st_esalts =
-        unsafe { vec_from_raw_parts(st_esalts_buf as *const generic_io_t, st_esalts_cnt) };
-
-    let bridge_parameter1 = unsafe { string_from_ptr(bridge_parameter1) };
-    let bridge_parameter2 = unsafe { string_from_ptr(bridge_parameter2) };
-    let bridge_parameter3 = unsafe { string_from_ptr(bridge_parameter3) };
-    let bridge_parameter4 = unsafe { string_from_ptr(bridge_parameter4) };
-
-    Box::into_raw(Box::new(ThreadContext {
-        module_name,
-        salts,
-        esalts,
-        st_salts,
-        st_esalts,
-        bridge_parameter1,
-        bridge_parameter2,
-        bridge_parameter3,
-        bridge_parameter4,
-    })) as *mut c_void
-}
 
-#[unsafe(no_mangle)]
-pub extern "C" fn drop_context(ctx: *mut c_void) {
-    assert!(!ctx.is_null());
-    unsafe {
-        drop(Box::from_raw(ctx as *mut ThreadContext));
+    fn thread_init(&self, ctx: &ThreadContext) -> Result<Self::ThreadState, String> {
+        Ok(generic_hash::thread_init(ctx))
     }
-}
 
-#[unsafe(no_mangle)]
-pub extern "C" fn get_info(buf: *mut c_char, buf_size: c_int) -> c_int {
-    assert!(buf_size > 0);
-    let info = INFO.get().unwrap_or(&"");
-    let n = info.len().min(buf_size as usize);
-    unsafe {
-        ptr::copy_nonoverlapping(info.as_ptr(), buf as *mut u8, n);
+    fn thread_term(&self, ctx: &ThreadContext, state: &mut Self::ThreadState) {
+        generic_hash::thread_term(ctx, state);
     }
-    n as c_int
-}
-
-#[unsafe(no_mangle)]
-pub extern "C" fn global_init(ctx: *mut bridge_context_t) -> bool {
-    assert!(!ctx.is_null());
-    let ctx = unsafe { &mut *ctx };
-    assert!(!ctx.dynlib_filename.is_null());
-
-    let dynlib_name = unsafe { string_from_ptr(ctx.dynlib_filename) };
-    let dynlib_name = Path::new(&dynlib_name)
-        .file_name()
-        .and_then(|x| x.to_str())
-        .unwrap_or_default();
-    let info = format!("Rust [{}]", dynlib_name);
-    INFO.set(info.leak()).expect("global_init called twice");
-    true
-}
 
-#[unsafe(no_mangle)]
-pub extern "C" fn global_term(_ctx: *mut bridge_context_t) {}
+    fn calc_hash(
+        &self,
+        state: &mut Self::ThreadState,
+        password: &[u8],
+        salt: &Salt,
+    ) -> Result<Vec<Output>, String> {
+        if !generic_hash::USE_VERIFY {
+            Ok(generic_hash::calc_hash(
+                state,
+                password,
+                salt.salt(),
+                salt.id,
+                salt.is_selftest,
+            ))
+        } else {
+            Ok(verify_digests(state, password, salt))
+        }
+    }
 
-#[unsafe(no_mangle)]
-pub extern "C" fn thread_init(ctx: *mut c_void) {
-    assert!(!ctx.is_null());
-    let ctx = unsafe { &mut *ctx.cast::<ThreadContext>() };
-    generic_hash::thread_init(ctx);
+    fn process_batch(
+        &self,
+        state: &mut Self::ThreadState,
+        passwords: &[&[u8]],
+        salt: &Salt,
+    ) -> Result<Vec<Vec<Output>>, String> {
+        if !generic_hash::USE_VERIFY {
+            let batch =
+                generic_hash::calc_batch(state, passwords, salt.salt(), salt.id, salt.is_selftest);
+            if let Some(batch) = batch {
+                return Ok(batch);
+            }
+        }
+        passwords
+            .iter()
+            .map(|password| self.calc_hash(state, password, salt))
+            .collect()
+    }
 }
 
-#[unsafe(no_mangle)]
-pub extern "C" fn thread_term(ctx: *mut c_void) {
-    assert!(!ctx.is_null());
-    let ctx = unsafe { &mut *ctx.cast::<ThreadContext>() };
-    generic_hash::thread_term(ctx);
-}
+export_bridge!(GenericHash);
+
+#[cfg(test)]
+mod tests {
+    use hashcat_bridge::hashcat_sys::{generic_io_t, salt_t};
+
+    use super::*;
+
+    fn esalt(hash: &[u8], salt: &[u8]) -> generic_io_t {
+        let mut esalt: generic_io_t = unsafe { std::mem::zeroed() };
+        unsafe {
+            std::ptr::copy_nonoverlapping(
+                hash.as_ptr(),
+                esalt.hash_buf.as_mut_ptr().cast(),
+                hash.len(),
+            );
+            std::ptr::copy_nonoverlapping(
+                salt.as_ptr(),
+                esalt.salt_buf.as_mut_ptr().cast(),
+                salt.len(),
+            );
+        }
+        esalt.hash_len = hash.len() as u32;
+        esalt.salt_len = salt.len() as u32;
+        esalt
+    }
 
-#[unsafe(no_mangle)]
-pub extern "C" fn kernel_loop(
-    ctx: *const c_void,
-    io: *mut generic_io_tmp_t,
-    pws_cnt: u64,
-    salt_id: c_int,
-    is_selftest: bool,
-) -> bool {
-    assert!(!ctx.is_null());
-    assert!(!io.is_null());
-    let io = unsafe { slice::from_raw_parts_mut(io, pws_cnt as usize) };
-
-    let ctx = unsafe { &*ctx.cast::<ThreadContext>() };
-
-    let results = process_batch(ctx, io, salt_id as usize, is_selftest);
-
-    assert_eq!(results.len(), pws_cnt as usize);
-
-    for (dst, src) in io.iter_mut().zip(results) {
-        dst.out_cnt = src.len() as u32;
-        assert!(
-            src.len() <= dst.out_buf.len(),
-            "calc_hash should return no more than {} hashes",
-            dst.out_buf.len()
+    #[test]
+    fn test_verify_digests() {
+        let salt = b"9348746780603343";
+        let hashcat = b"33522b0fd9812aa68586f66dba7c17a8ce64344137f9c7d8b11f32a6921c22de";
+        let raw: salt_t = unsafe { std::mem::zeroed() };
+        let esalts = [
+            esalt(&[b'0'; 64], salt),
+            esalt(hashcat, salt),
+            esalt(&hashcat.to_ascii_uppercase(), salt),
+        ];
+        let salt = Salt {
+            id: 0,
+            is_selftest: false,
+            raw: &raw,
+            esalts: &esalts,
+        };
+        let mut state = generic_hash::ThreadState::default();
+        assert_eq!(
+            verify_digests(&mut state, b"hashcat", &salt),
+            [
+                Output::Text(hashcat.to_vec()),
+                Output::Text(hashcat.to_ascii_uppercase())
+            ]
         );
-        for (s, (buf, len)) in src
-            .iter()
-            .zip(dst.out_buf.iter_mut().zip(dst.out_len.iter_mut()))
-        {
-            assert!(s.len() <= mem::size_of_val(buf), "digest size too big");
-            unsafe {
-                ptr::copy_nonoverlapping(s.as_ptr(), buf.as_mut_ptr() as *mut u8, s.len());
-            }
-            *len = s.len() as u32;
-        }
+        assert!(verify_digests(&mut state, b"hashcat!", &salt).is_empty());
     }
-    true
-}
 
-fn process_batch(
-    ctx: &ThreadContext,
-    io: &[generic_io_tmp_t],
-    salt_id: usize,
-    is_selftest: bool,
-) -> Vec<Vec<String>> {
-    let esalt = ctx.get_raw_esalt(salt_id, is_selftest);
-    let salt = unsafe {
-        slice::from_raw_parts(
-            esalt.salt_buf.as_ptr() as *const u8,
-            esalt.salt_len as usize,
-        )
-    };
-    io.iter()
-        .map(|x| {
-            let pw =
-                unsafe { slice::from_raw_parts(x.pw_buf.as_ptr() as *const u8, x.pw_len as usize) };
-            generic_hash::calc_hash(pw, salt)
-        })
-        .collect()
+    #[test]
+    fn test_verify_digests_max_outputs() {
+        let salt = b"9348746780603343";
+        let hashcat = b"33522b0fd9812aa68586f66dba7c17a8ce64344137f9c7d8b11f32a6921c22de";
+        let raw: salt_t = unsafe { std::mem::zeroed() };
+        let esalts = vec![esalt(hashcat, salt); generic_io_tmp_t::MAX_OUTPUTS + 8];
+        let salt = Salt {
+            id: 0,
+            is_selftest: false,
+            raw: &raw,
+            esalts: &esalts,
+        };
+        let mut state = generic_hash::ThreadState::default();
+        assert_eq!(
+            verify_digests(&mut state, b"hashcat", &salt).len(),
+            generic_io_tmp_t::MAX_OUTPUTS
+        );
+    }
 }
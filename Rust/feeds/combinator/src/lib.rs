@@ -0,0 +1,309 @@
+/**
+ * Author......: See docs/credits.txt
+ * License.....: MIT
+ */
+use hashcat_feed::{
+    export_feed, generic_plugin_options_t, Feed, GlobalContext, Wordlist,
+    GENERIC_PLUGIN_OPTIONS_ICONV, GENERIC_PLUGIN_OPTIONS_RULES, PW_MAX,
+};
+
+#[cfg(test)]
+mod tests;
+
+const USAGE: &str = "usage: rust_combinator.so [-s SEPARATOR...] [--separator-file FILE] \
+                     [-c SLOT:CASES...] WORDLIST WORDLIST [WORDLIST...]";
+
+/// A case transformation applied to the word of a slot, named after the rule
+/// function doing the same.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Case {
+    /// `:` keeps the word as it is.
+    Keep,
+    /// `l` lowercases all letters.
+    Lower,
+    /// `u` uppercases all letters.
+    Upper,
+    /// `c` uppercases the first letter and lowercases the rest.
+    Capitalize,
+    /// `C` lowercases the first letter and uppercases the rest.
+    InvertCapitalize,
+    /// `t` toggles the case of all letters.
+    Toggle,
+}
+
+impl Case {
+    fn parse(c: u8) -> Option<Self> {
+        Some(match c {
+            b':' => Self::Keep,
+            b'l' => Self::Lower,
+            b'u' => Self::Upper,
+            b'c' => Self::Capitalize,
+            b'C' => Self::InvertCapitalize,
+            b't' => Self::Toggle,
+            _ => return None,
+        })
+    }
+
+    fn apply(self, word: &mut [u8]) {
+        match self {
+            Self::Keep => {}
+            Self::Lower => word.make_ascii_lowercase(),
+            Self::Upper => word.make_ascii_uppercase(),
+            Self::Capitalize | Self::InvertCapitalize => {
+                let Some((first, rest)) = word.split_first_mut() else {
+                    return;
+                };
+                if self == Self::Capitalize {
+                    first.make_ascii_uppercase();
+                    rest.make_ascii_lowercase();
+                } else {
+                    first.make_ascii_lowercase();
+                    rest.make_ascii_uppercase();
+                }
+            }
+            Self::Toggle => {
+                for c in word {
+                    if c.is_ascii_alphabetic() {
+                        *c ^= 0x20;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// The command line of the feed.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Args {
+    separators: Vec<Vec<u8>>,
+    separator_file: Option<String>,
+    /// Slot index and case transformations, in the order given.
+    cases: Vec<(usize, Vec<Case>)>,
+    wordlists: Vec<String>,
+}
+
+impl Args {
+    fn parse(args: &[String]) -> Result<Self, String> {
+        let mut parsed = Self::default();
+        let mut iter = args.iter();
+        let mut options = true;
+        while let Some(arg) = iter.next() {
+            if !options || !arg.starts_with('-') || arg == "-" {
+                parsed.wordlists.push(arg.clone());
+                continue;
+            }
+            if arg == "--" {
+                options = false;
+                continue;
+            }
+            let (name, inline) = match arg.split_once('=') {
+                Some((name, value)) if arg.starts_with("--") => (name, Some(value)),
+                _ => (arg.as_str(), None),
+            };
+            let value = inline
+                .map(str::to_string)
+                .or_else(|| iter.next().cloned())
+                .ok_or_else(|| format!("Option {} requires a value.\n{}", name, USAGE))?;
+            match name {
+                "-s" | "--separator" => parsed.separators.push(value.into_bytes()),
+                "--separator-file" => parsed.separator_file = Some(value),
+                "-c" | "--case" => parsed.cases.push(parse_cases(&value)?),
+                _ => return Err(format!("Unknown option {}.\n{}", arg, USAGE)),
+            }
+        }
+        Ok(parsed)
+    }
+}
+
+/// Parses `SLOT:CASES`, e.g. `1:lc` to try the first word lowercased and
+/// capitalized.
+fn parse_cases(value: &str) -> Result<(usize, Vec<Case>), String> {
+    let invalid = || format!("Invalid case transformation '{}'.\n{}", value, USAGE);
+    let (slot, cases) = value.split_once(':').ok_or_else(invalid)?;
+    let slot: usize = slot.parse().map_err(|_| invalid())?;
+    let cases = cases
+        .bytes()
+        .map(Case::parse)
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(invalid)?;
+    if slot == 0 || cases.is_empty() {
+        return Err(invalid());
+    }
+    Ok((slot - 1, cases))
+}
+
+/// One word of the combination: the wordlist it is taken from and the case
+/// transformations tried on each word.
+#[derive(Debug)]
+struct Slot {
+    list: usize,
+    cases: Vec<Case>,
+}
+
+/// Combines the words of any number of wordlists like attack mode 1 does for
+/// two, optionally joined by separators.
+///
+/// The candidates are enumerated in mixed radix over the digits
+/// `word 1, separator 1, word 2, ..., word n`, the last changing fastest, so
+/// any offset maps directly to a candidate. The digit of a slot covers its
+/// words times its case transformations, the transformations changing
+/// fastest. Transformations that leave a word unchanged are not filtered, so
+/// the keyspace stays exact.
+struct Combinator {
+    /// Each wordlist once, even if it is used by several slots.
+    lists: Vec<Wordlist>,
+    slots: Vec<Slot>,
+    /// Empty when no separators are given.
+    separators: Vec<Vec<u8>>,
+    radixes: Vec<u64>,
+    keyspace: u64,
+}
+
+impl Combinator {
+    fn new(args: &Args) -> Result<Self, String> {
+        if args.wordlists.len() < 2 {
+            return Err(format!("At least two wordlists are required.\n{}", USAGE));
+        }
+
+        let mut paths: Vec<&String> = Vec::new();
+        let mut lists = Vec::new();
+        let mut slots = Vec::new();
+        for path in &args.wordlists {
+            let list = match paths.iter().position(|p| *p == path) {
+                Some(list) => list,
+                None => {
+                    let wordlist = Wordlist::load(path)?;
+                    if wordlist.is_empty() {
+                        return Err(format!("{}: empty file.", path));
+                    }
+                    paths.push(path);
+                    lists.push(wordlist);
+                    lists.len() - 1
+                }
+            };
+            slots.push(Slot {
+                list,
+                cases: vec![Case::Keep],
+            });
+        }
+        for (slot, cases) in &args.cases {
+            let cnt = slots.len();
+            let slot = slots.get_mut(*slot).ok_or_else(|| {
+                format!(
+                    "Invalid slot {} in case transformation, there are {} wordlists.",
+                    slot + 1,
+                    cnt
+                )
+            })?;
+            slot.cases = cases.clone();
+        }
+
+        let mut separators = args.separators.clone();
+        if let Some(path) = &args.separator_file {
+            let wordlist = Wordlist::load(path)?;
+            if wordlist.is_empty() {
+                return Err(format!("{}: empty file.", path));
+            }
+            separators.extend(wordlist.iter().map(|sep| sep.into_owned()));
+        }
+
+        let mut radixes = Vec::new();
+        for (index, slot) in slots.iter().enumerate() {
+            if index > 0 && !separators.is_empty() {
+                radixes.push(separators.len() as u64);
+            }
+            radixes.push(lists[slot.list].len() as u64 * slot.cases.len() as u64);
+        }
+        let keyspace = radixes
+            .iter()
+            .try_fold(1u64, |n, &radix| n.checked_mul(radix))
+            .ok_or("Integer overflow detected in keyspace of combination.")?;
+
+        Ok(Self {
+            lists,
+            slots,
+            separators,
+            radixes,
+            keyspace,
+        })
+    }
+}
+
+/// Position of a generator thread: the current digit of each slot and
+/// separator, and the buffer the candidate is assembled in.
+struct Cursor {
+    digits: Vec<u64>,
+    done: bool,
+    buf: Vec<u8>,
+}
+
+impl Feed for Combinator {
+    type ThreadState = Cursor;
+
+    const PLUGIN_OPTIONS: generic_plugin_options_t =
+        GENERIC_PLUGIN_OPTIONS_ICONV | GENERIC_PLUGIN_OPTIONS_RULES;
+
+    fn global_init(ctx: &GlobalContext) -> Result<Self, String> {
+        Self::new(&Args::parse(&ctx.args)?)
+    }
+
+    fn keyspace(&self) -> Option<u64> {
+        Some(self.keyspace)
+    }
+
+    fn thread_init(&self) -> Result<Cursor, String> {
+        Ok(Cursor {
+            digits: vec![0; self.radixes.len()],
+            done: false,
+            buf: Vec::new(),
+        })
+    }
+
+    fn seek(&self, cursor: &mut Cursor, offset: u64) -> Result<(), String> {
+        cursor.done = offset >= self.keyspace;
+        let mut rem = offset;
+        for (digit, &radix) in cursor.digits.iter_mut().zip(&self.radixes).rev() {
+            *digit = rem % radix;
+            rem /= radix;
+        }
+        Ok(())
+    }
+
+    fn next(&self, cursor: &mut Cursor, out: &mut [u8; PW_MAX]) -> Result<Option<usize>, String> {
+        if cursor.done {
+            return Ok(None);
+        }
+
+        cursor.buf.clear();
+        let mut digits = cursor.digits.iter();
+        for (index, slot) in self.slots.iter().enumerate() {
+            if index > 0 && !self.separators.is_empty() {
+                let sep = *digits.next().unwrap() as usize;
+                cursor.buf.extend_from_slice(&self.separators[sep]);
+            }
+            let digit = *digits.next().unwrap();
+            let cnt = slot.cases.len() as u64;
+            let start = cursor.buf.len();
+            cursor
+                .buf
+                .extend_from_slice(&self.lists[slot.list].word((digit / cnt) as usize));
+            slot.cases[(digit % cnt) as usize].apply(&mut cursor.buf[start..]);
+        }
+        let len = cursor.buf.len().min(PW_MAX);
+        out[..len].copy_from_slice(&cursor.buf[..len]);
+
+        cursor.done = true;
+        for (digit, &radix) in cursor.digits.iter_mut().zip(&self.radixes).rev() {
+            *digit += 1;
+            if *digit < radix {
+                cursor.done = false;
+                break;
+            }
+            *digit = 0;
+        }
+
+        Ok(Some(len))
+    }
+}
+
+export_feed!(Combinator);
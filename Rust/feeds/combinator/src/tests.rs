@@ -0,0 +1,146 @@
+/**
+ * Author......: See docs/credits.txt
+ * License.....: MIT
+ */
+use std::fs;
+
+use hashcat_feed::{
+    testing::{candidates, temp_file},
+    Feed, PW_MAX,
+};
+
+use crate::{parse_cases, Args, Case, Combinator};
+
+fn combinator(args: &[&str]) -> Result<Combinator, String> {
+    let args: Vec<String> = args.iter().map(|s| s.to_string()).collect();
+    Combinator::new(&Args::parse(&args)?)
+}
+
+#[test]
+fn test_cases() {
+    let apply = |case, word: &str| {
+        let mut word = word.as_bytes().to_vec();
+        Case::apply(case, &mut word);
+        String::from_utf8(word).unwrap()
+    };
+    assert_eq!(apply(Case::Keep, "pAss1"), "pAss1");
+    assert_eq!(apply(Case::Lower, "pAss1"), "pass1");
+    assert_eq!(apply(Case::Upper, "pAss1"), "PASS1");
+    assert_eq!(apply(Case::Capitalize, "pAss1"), "Pass1");
+    assert_eq!(apply(Case::InvertCapitalize, "pAss1"), "pASS1");
+    assert_eq!(apply(Case::Toggle, "pAss1"), "PaSS1");
+    assert_eq!(apply(Case::Capitalize, ""), "");
+
+    assert_eq!(
+        parse_cases("2:lc").unwrap(),
+        (1, vec![Case::Lower, Case::Capitalize])
+    );
+    for value in ["lc", "0:l", "1:", "1:x", "x:l"] {
+        assert!(parse_cases(value).is_err(), "{}", value);
+    }
+}
+
+#[test]
+fn test_combine() {
+    let colors = temp_file("colors", "red\r\nblue\n");
+    let animals = temp_file("animals", "cat\ndog\n$HEX[6f7774]\n");
+    let colors = colors.to_str().unwrap();
+    let animals = animals.to_str().unwrap();
+
+    let feed = combinator(&[colors, animals]).unwrap();
+    assert_eq!(feed.keyspace(), Some(6));
+    assert_eq!(feed.lists.len(), 2);
+    assert_eq!(
+        candidates(&feed, 0, 10),
+        ["redcat", "reddog", "redowt", "bluecat", "bluedog", "blueowt"]
+    );
+
+    // The same list repeated is loaded once.
+    let feed = combinator(&[colors, colors, colors, colors]).unwrap();
+    assert_eq!(feed.keyspace(), Some(16));
+    assert_eq!(feed.lists.len(), 1);
+    assert_eq!(candidates(&feed, 1, 2), ["redredredblue", "redredbluered"]);
+    assert_eq!(candidates(&feed, 15, 2), ["blueblueblueblue"]);
+
+    let feed = combinator(&["-s", "-", "--separator=", colors, animals, colors]).unwrap();
+    assert_eq!(feed.keyspace(), Some(2 * 2 * 3 * 2 * 2));
+    assert_eq!(
+        candidates(&feed, 0, 5),
+        [
+            "red-cat-red",
+            "red-cat-blue",
+            "red-catred",
+            "red-catblue",
+            "red-dog-red"
+        ]
+    );
+
+    let seps = temp_file("seps", " \n\n$HEX[2e]\n");
+    let feed = combinator(&[
+        "--separator-file",
+        seps.to_str().unwrap(),
+        "-c",
+        "1:uc",
+        "--case=2::t",
+        colors,
+        animals,
+    ])
+    .unwrap();
+    assert_eq!(feed.keyspace(), Some(2 * 2 * 3 * 3 * 2));
+    assert_eq!(
+        candidates(&feed, 0, 7),
+        ["RED cat", "RED CAT", "RED dog", "RED DOG", "RED owt", "RED OWT", "REDcat"]
+    );
+    assert_eq!(candidates(&feed, 18 + 2 * 6 + 5, 1), ["Red.OWT"]);
+
+    assert!(combinator(&[colors]).is_err());
+    assert!(combinator(&["-c", "3:l", colors, animals]).is_err());
+    assert!(combinator(&["--sep", "-", colors, animals]).is_err());
+    let empty = temp_file("empty", "");
+    assert_eq!(
+        combinator(&[colors, empty.to_str().unwrap()])
+            .err()
+            .unwrap(),
+        format!("{}: empty file.", empty.display())
+    );
+
+    for path in [
+        colors,
+        animals,
+        seps.to_str().unwrap(),
+        empty.to_str().unwrap(),
+    ] {
+        fs::remove_file(path).unwrap();
+    }
+}
+
+#[test]
+fn test_seek() {
+    let words = temp_file("seek", "a\nbb\nccc\n");
+    let words = words.to_str().unwrap();
+    let feed = combinator(&["-s", "_", "-s", "", "-c", "2::u", words, words, words]).unwrap();
+    let keyspace = feed.keyspace().unwrap();
+    assert_eq!(keyspace, 3 * 2 * 6 * 2 * 3);
+
+    let all = candidates(&feed, 0, keyspace as usize + 1);
+    assert_eq!(all.len() as u64, keyspace);
+    for offset in [0, 1, 7, 35, 36, 100, keyspace - 1] {
+        assert_eq!(
+            candidates(&feed, offset, 3),
+            all[offset as usize..]
+                .iter()
+                .take(3)
+                .cloned()
+                .collect::<Vec<_>>()
+        );
+    }
+    assert!(candidates(&feed, keyspace, 1).is_empty());
+
+    let long = temp_file("long", "a".repeat(200));
+    let long = long.to_str().unwrap();
+    let feed = combinator(&[long, long]).unwrap();
+    assert_eq!(candidates(&feed, 0, 1)[0].len(), PW_MAX);
+
+    fs::remove_file(words).unwrap();
+    fs::remove_file(long).unwrap();
+}
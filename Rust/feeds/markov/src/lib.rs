@@ -0,0 +1,263 @@
+/**
+ * Author......: See docs/credits.txt
+ * License.....: MIT
+ */
+use std::{cmp::Reverse, path::Path};
+
+use hashcat_feed::{
+    charset::{Charset, Charsets, CUSTOM_CNT},
+    export_feed, generic_plugin_options_t, Feed, GlobalContext, GENERIC_PLUGIN_OPTIONS_ICONV,
+    GENERIC_PLUGIN_OPTIONS_RULES, PW_MAX,
+};
+
+use hcstat2::{Hcstat2, CHARSIZ, SP_HCSTAT};
+
+#[cfg(test)]
+mod tests;
+
+const USAGE: &str = "usage: rust_markov.so [-1..-8 CHARSET] [-t N] [--markov-hcstat2=FILE] \
+                     [--markov-classic] [--markov-disable] [--markov-inverse] MASK";
+
+/// The command line of the feed, following the hashcat options of the same
+/// name.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Args {
+    custom_charsets: [Option<Vec<u8>>; CUSTOM_CNT],
+    threshold: usize,
+    hcstat: Option<String>,
+    classic: bool,
+    disable: bool,
+    inverse: bool,
+    mask: Option<String>,
+}
+
+impl Args {
+    fn parse(args: &[String]) -> Result<Self, String> {
+        let mut parsed = Self::default();
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            if !arg.starts_with('-') || arg == "-" {
+                if parsed.mask.is_some() {
+                    return Err(format!("Unexpected argument {}.\n{}", arg, USAGE));
+                }
+                parsed.mask = Some(arg.clone());
+                continue;
+            }
+            match arg.as_str() {
+                "--markov-classic" => {
+                    parsed.classic = true;
+                    continue;
+                }
+                "--markov-disable" => {
+                    parsed.disable = true;
+                    continue;
+                }
+                "--markov-inverse" => {
+                    parsed.inverse = true;
+                    continue;
+                }
+                _ => {}
+            }
+            let (name, inline) = match arg.split_once('=') {
+                Some((name, value)) if arg.starts_with("--") => (name, Some(value)),
+                _ => (arg.as_str(), None),
+            };
+            let value = inline
+                .map(str::to_string)
+                .or_else(|| iter.next().cloned())
+                .ok_or_else(|| format!("Option {} requires a value.\n{}", name, USAGE))?;
+            let index = match name.as_bytes() {
+                [b'-', c @ b'1'..=b'8'] => Some(c - b'1'),
+                [b'-', b'-', rest @ .., c @ b'1'..=b'8'] if rest == b"custom-charset" => {
+                    Some(c - b'1')
+                }
+                _ => None,
+            };
+            if let Some(index) = index {
+                parsed.custom_charsets[index as usize] = Some(value.into_bytes());
+                continue;
+            }
+            match name {
+                "-t" | "--markov-threshold" => {
+                    parsed.threshold = value
+                        .parse()
+                        .map_err(|_| format!("Invalid {} value specified.", name))?
+                }
+                "--markov-hcstat2" => parsed.hcstat = Some(value),
+                _ => return Err(format!("Unknown option {}.\n{}", arg, USAGE)),
+            }
+        }
+        Ok(parsed)
+    }
+}
+
+/// The characters of `cs` ordered by their count in `stats`, most frequent
+/// first, and cut off at `threshold`. Ties keep the order of the character
+/// codes, like the merge sort behind `qsort()` in `sp_setup_tbl()`.
+fn order(stats: Option<&[u64]>, inverse: bool, cs: &Charset, threshold: usize) -> Vec<u8> {
+    let count = |c: u8| match stats {
+        None => 0,
+        Some(stats) if inverse => !stats[c as usize],
+        Some(stats) => stats[c as usize],
+    };
+    let mut allowed = [false; CHARSIZ];
+    for &c in cs {
+        allowed[c as usize] = true;
+    }
+    let mut chars: Vec<u8> = (0..=u8::MAX).filter(|&c| allowed[c as usize]).collect();
+    chars.sort_by_key(|&c| Reverse(count(c)));
+    chars.truncate(threshold);
+    chars
+}
+
+/// Generates the candidates of a mask in Markov order, like attack mode 3
+/// does with the statistics of a `.hcstat2` file.
+///
+/// The first position takes its characters ordered by the root statistics,
+/// each following position by the statistics of the character before it.
+/// With a threshold, only the most frequent characters are tried on each
+/// position, so every position has a fixed number of choices and the
+/// candidates are enumerated in mixed radix, the first position changing
+/// fastest as in `sp_exec()`.
+struct Markov {
+    /// Characters of the first position, in order.
+    root: Vec<u8>,
+    /// For each position after the first, the characters following each
+    /// previous character, `radixes[pos]` of them per character.
+    next: Vec<Vec<u8>>,
+    radixes: Vec<usize>,
+    keyspace: u64,
+}
+
+impl Markov {
+    fn new(args: &Args, shared_dir: &Path) -> Result<Self, String> {
+        let mask = args
+            .mask
+            .as_ref()
+            .ok_or_else(|| format!("No mask specified.\n{}", USAGE))?;
+        let mut charsets = Charsets::default();
+        for (index, cs) in args.custom_charsets.iter().enumerate() {
+            if let Some(cs) = cs {
+                charsets.define(index, cs)?;
+            }
+        }
+        let css = charsets.parse(mask.as_bytes())?;
+
+        let threshold = match args.threshold {
+            0 => CHARSIZ,
+            threshold => threshold,
+        };
+        let stats = if args.disable {
+            None
+        } else {
+            let path = match &args.hcstat {
+                Some(path) => path.clone(),
+                None => shared_dir.join(SP_HCSTAT).to_string_lossy().into_owned(),
+            };
+            // In classic mode the statistics of all positions are added up and
+            // apply to each.
+            Some(if args.classic {
+                Hcstat2::read(&path)?.classic()
+            } else {
+                Hcstat2::read_positions(&path, css.len())?
+            })
+        };
+
+        let root = order(
+            stats.as_ref().map(|s| s.root(0)),
+            args.inverse,
+            &css[0],
+            threshold,
+        );
+        let mut next = Vec::new();
+        for (pos, cs) in css.iter().enumerate().skip(1) {
+            let pos = if args.classic { 0 } else { pos - 1 };
+            let mut chars = Vec::new();
+            for prev in 0..=u8::MAX {
+                let stats = stats.as_ref().map(|s| s.markov(pos, prev));
+                chars.extend(order(stats, args.inverse, cs, threshold));
+            }
+            next.push(chars);
+        }
+
+        let radixes: Vec<usize> = css.iter().map(|cs| cs.len().min(threshold)).collect();
+        let keyspace = radixes
+            .iter()
+            .try_fold(1u64, |n, &radix| n.checked_mul(radix as u64))
+            .ok_or_else(|| format!("Integer overflow detected in keyspace of mask: {}", mask))?;
+
+        Ok(Self {
+            root,
+            next,
+            radixes,
+            keyspace,
+        })
+    }
+}
+
+/// Position of a generator thread: the index of the current character of
+/// each position among the characters allowed after the previous one.
+struct Cursor {
+    digits: Vec<usize>,
+    done: bool,
+}
+
+impl Feed for Markov {
+    type ThreadState = Cursor;
+
+    const PLUGIN_OPTIONS: generic_plugin_options_t =
+        GENERIC_PLUGIN_OPTIONS_ICONV | GENERIC_PLUGIN_OPTIONS_RULES;
+
+    fn global_init(ctx: &GlobalContext) -> Result<Self, String> {
+        Self::new(&Args::parse(&ctx.args)?, &ctx.shared_dir)
+    }
+
+    fn keyspace(&self) -> Option<u64> {
+        Some(self.keyspace)
+    }
+
+    fn thread_init(&self) -> Result<Cursor, String> {
+        Ok(Cursor {
+            digits: vec![0; self.radixes.len()],
+            done: false,
+        })
+    }
+
+    fn seek(&self, cursor: &mut Cursor, offset: u64) -> Result<(), String> {
+        cursor.done = offset >= self.keyspace;
+        let mut rem = offset;
+        for (digit, &radix) in cursor.digits.iter_mut().zip(&self.radixes) {
+            *digit = (rem % radix as u64) as usize;
+            rem /= radix as u64;
+        }
+        Ok(())
+    }
+
+    fn next(&self, cursor: &mut Cursor, out: &mut [u8; PW_MAX]) -> Result<Option<usize>, String> {
+        if cursor.done {
+            return Ok(None);
+        }
+
+        let mut c = self.root[cursor.digits[0]];
+        out[0] = c;
+        for (pos, next) in self.next.iter().enumerate() {
+            let radix = self.radixes[pos + 1];
+            c = next[c as usize * radix + cursor.digits[pos + 1]];
+            out[pos + 1] = c;
+        }
+
+        cursor.done = true;
+        for (digit, &radix) in cursor.digits.iter_mut().zip(&self.radixes) {
+            *digit += 1;
+            if *digit < radix {
+                cursor.done = false;
+                break;
+            }
+            *digit = 0;
+        }
+
+        Ok(Some(self.radixes.len()))
+    }
+}
+
+export_feed!(Markov);
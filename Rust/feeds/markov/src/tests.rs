@@ -0,0 +1,144 @@
+/**
+ * Author......: See docs/credits.txt
+ * License.....: MIT
+ */
+use std::{collections::HashSet, env, fs, path::Path, process};
+
+use hashcat_feed::{
+    testing::{candidates, temp_file},
+    Feed,
+};
+use hcstat2::Hcstat2;
+
+use crate::{Args, Markov};
+
+const REPO: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../../..");
+
+fn hcstat() -> String {
+    format!("{}/hashcat.hcstat2", REPO)
+}
+
+fn markov(args: &[&str]) -> Result<Markov, String> {
+    let args: Vec<String> = args.iter().map(|s| s.to_string()).collect();
+    Markov::new(&Args::parse(&args)?, Path::new(REPO))
+}
+
+#[test]
+fn test_args() {
+    let args = |args: &[&str]| {
+        let args: Vec<String> = args.iter().map(|s| s.to_string()).collect();
+        Args::parse(&args)
+    };
+    let parsed = args(&["-1", "?l?d", "-t", "5", "--markov-classic", "?1?1"]).unwrap();
+    assert_eq!(parsed.custom_charsets[0].as_deref(), Some(&b"?l?d"[..]));
+    assert_eq!(parsed.threshold, 5);
+    assert!(parsed.classic && !parsed.inverse && !parsed.disable);
+    assert_eq!(parsed.mask.as_deref(), Some("?1?1"));
+    let parsed = args(&["--markov-threshold=2", "--markov-hcstat2=x.hcstat2", "?d"]).unwrap();
+    assert_eq!(parsed.threshold, 2);
+    assert_eq!(parsed.hcstat.as_deref(), Some("x.hcstat2"));
+
+    assert!(args(&["-t", "x"]).is_err());
+    assert!(args(&["-t"]).is_err());
+    assert!(args(&["?d", "?d"]).is_err());
+    assert!(args(&["--increment"]).is_err());
+}
+
+#[test]
+fn test_disabled() {
+    let feed = markov(&["--markov-disable", "?l?d"]).unwrap();
+    assert_eq!(feed.keyspace(), Some(260));
+    assert_eq!(candidates(&feed, 0, 3), ["a0", "b0", "c0"]);
+    assert_eq!(candidates(&feed, 25, 2), ["z0", "a1"]);
+    assert_eq!(candidates(&feed, 259, 2), ["z9"]);
+
+    let feed = markov(&["--markov-disable", "-t", "2", "-1", "xy?d", "?1?1?1"]).unwrap();
+    assert_eq!(feed.keyspace(), Some(8));
+    assert_eq!(
+        candidates(&feed, 0, 10),
+        ["000", "100", "010", "110", "001", "101", "011", "111"]
+    );
+
+    assert!(markov(&["--markov-disable"]).is_err());
+    assert!(markov(&["--markov-disable", "?1"]).is_err());
+    // An empty charset leaves a position without characters.
+    assert_eq!(
+        markov(&["--markov-disable", "-1", "", "?d?1"])
+            .err()
+            .unwrap(),
+        "Custom-charset 1 is empty."
+    );
+    assert!(markov(&["--markov-disable", "?b?b?b?b?b?b?b?b"]).is_err());
+}
+
+#[test]
+fn test_hcstat() {
+    let stats = Hcstat2::read_positions(&hcstat(), 4).unwrap();
+    let most_frequent = (b'a'..=b'z').max_by_key(|&c| stats.root(0)[c as usize]);
+
+    let feed = markov(&["--markov-hcstat2", &hcstat(), "?l?l?l?l"]).unwrap();
+    let keyspace = feed.keyspace().unwrap();
+    assert_eq!(keyspace, 26 * 26 * 26 * 26);
+    let all = candidates(&feed, 0, keyspace as usize + 1);
+    assert_eq!(all.len() as u64, keyspace);
+    assert_eq!(all.iter().collect::<HashSet<_>>().len() as u64, keyspace);
+    assert_eq!(all[0].as_bytes()[0], most_frequent.unwrap());
+    for offset in [1, 25, 26, 1000, 12345, keyspace - 1] {
+        assert_eq!(
+            candidates(&feed, offset, 3),
+            all[offset as usize..]
+                .iter()
+                .take(3)
+                .cloned()
+                .collect::<Vec<_>>()
+        );
+    }
+
+    // The second character is the most frequent one following the first.
+    let first = all[0].as_bytes()[0];
+    let follow = (b'a'..=b'z').max_by_key(|&c| stats.markov(0, first)[c as usize]);
+    assert_eq!(all[0].as_bytes()[1], follow.unwrap());
+
+    let feed = markov(&["--markov-hcstat2", &hcstat(), "-t", "4", "?l?l?l?l"]).unwrap();
+    assert_eq!(feed.keyspace(), Some(4 * 4 * 4 * 4));
+    let top = candidates(&feed, 0, 256);
+    assert_eq!(top[..4], all[..4]);
+    assert_eq!(top.iter().collect::<HashSet<_>>().len(), 256);
+
+    let classic = markov(&[
+        "--markov-hcstat2",
+        &hcstat(),
+        "--markov-classic",
+        "?l?l?l?l",
+    ]);
+    assert_ne!(candidates(&classic.unwrap(), 0, 100), all[..100]);
+
+    // Without --markov-hcstat2 the file in the shared dir is used, like -a 3.
+    let default = markov(&["?l?l?l?l"]).unwrap();
+    assert_eq!(candidates(&default, 0, 100), all[..100]);
+    let shared_dir = env::temp_dir().join(format!("markov_{}_missing", process::id()));
+    assert!(Markov::new(&Args::parse(&["?d".into()]).unwrap(), &shared_dir).is_err());
+
+    let feed = markov(&["--markov-hcstat2", &hcstat(), "?l"]).unwrap();
+    let inverse = markov(&["--markov-hcstat2", &hcstat(), "--markov-inverse", "?l"]).unwrap();
+    let mut reversed = candidates(&feed, 0, 26);
+    reversed.reverse();
+    assert_eq!(candidates(&inverse, 0, 26), reversed);
+}
+
+#[test]
+fn test_invalid_hcstat() {
+    let path = temp_file("invalid.hcstat2", b"\x01\x00\x0fhcstat2 invalid!\x00");
+    let path = path.to_str().unwrap();
+    assert_eq!(
+        markov(&["--markov-hcstat2", path, "?d"]).err().unwrap(),
+        format!("{}: Invalid header", path)
+    );
+    fs::write(path, b"not lzma2").unwrap();
+    assert_eq!(
+        markov(&["--markov-hcstat2", path, "?d"]).err().unwrap(),
+        format!("{}: Could not uncompress data.", path)
+    );
+    fs::remove_file(path).unwrap();
+    assert!(markov(&["--markov-hcstat2", path, "?d"]).is_err());
+}
@@ -0,0 +1,269 @@
+/**
+ * Author......: See docs/credits.txt
+ * License.....: MIT
+ */
+use std::{fs, path::Path};
+
+use hashcat_feed::{
+    charset::{mask_len, truncate_mask, Charset, Charsets, MaskLine, CUSTOM_CNT},
+    export_feed, generic_plugin_options_t, Feed, GlobalContext, GENERIC_PLUGIN_OPTIONS_ICONV,
+    GENERIC_PLUGIN_OPTIONS_RULES, PW_MAX,
+};
+
+#[cfg(test)]
+mod tests;
+
+const USAGE: &str = "usage: rust_mask.so [-1..-8 CHARSET] [-i] [--increment-min=N] \
+                     [--increment-max=N] [MASK | FILE.hcmask...]";
+
+/// The command line of the feed, following the hashcat options of the same
+/// name.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Args {
+    custom_charsets: [Option<Vec<u8>>; CUSTOM_CNT],
+    increment: bool,
+    increment_min: Option<usize>,
+    increment_max: Option<usize>,
+    masks: Vec<String>,
+}
+
+impl Args {
+    fn parse(args: &[String]) -> Result<Self, String> {
+        let mut parsed = Self::default();
+        let mut iter = args.iter();
+        let mut options = true;
+        while let Some(arg) = iter.next() {
+            if !options || !arg.starts_with('-') || arg == "-" {
+                parsed.masks.push(arg.clone());
+                continue;
+            }
+            if arg == "--" {
+                options = false;
+                continue;
+            }
+            if arg == "-i" || arg == "--increment" {
+                parsed.increment = true;
+                continue;
+            }
+            let (name, inline) = match arg.split_once('=') {
+                Some((name, value)) if arg.starts_with("--") => (name, Some(value)),
+                _ => (arg.as_str(), None),
+            };
+            let mut value = || {
+                inline
+                    .map(str::to_string)
+                    .or_else(|| iter.next().cloned())
+                    .ok_or_else(|| format!("Option {} requires a value.\n{}", name, USAGE))
+            };
+            let index = match name.as_bytes() {
+                [b'-', c @ b'1'..=b'8'] => Some(c - b'1'),
+                [b'-', b'-', rest @ .., c @ b'1'..=b'8'] if rest == b"custom-charset" => {
+                    Some(c - b'1')
+                }
+                _ => None,
+            };
+            if let Some(index) = index {
+                parsed.custom_charsets[index as usize] = Some(value()?.into_bytes());
+                continue;
+            }
+            match name {
+                "--increment-min" => parsed.increment_min = Some(parse_len(name, &value()?)?),
+                "--increment-max" => parsed.increment_max = Some(parse_len(name, &value()?)?),
+                _ => return Err(format!("Unknown option {}.\n{}", arg, USAGE)),
+            }
+        }
+        Ok(parsed)
+    }
+}
+
+fn parse_len(name: &str, value: &str) -> Result<usize, String> {
+    match value.parse() {
+        Ok(len) if (1..=PW_MAX).contains(&len) => Ok(len),
+        _ => Err(format!("Invalid {} value specified.", name)),
+    }
+}
+
+/// One mask of the session, expanded to its charsets.
+#[derive(Debug)]
+struct Entry {
+    css: Vec<Charset>,
+    /// Offset of the first candidate of this mask in the whole keyspace.
+    start: u64,
+}
+
+/// Generates the candidates of one or more masks like attack mode 3. The
+/// candidates of a mask are enumerated in mixed radix with the last position
+/// changing fastest, so any offset maps directly to a candidate.
+struct Mask {
+    entries: Vec<Entry>,
+    keyspace: u64,
+}
+
+impl Mask {
+    fn new(args: &Args) -> Result<Self, String> {
+        let mut args_cs = args.custom_charsets.clone();
+        let mut masks = Vec::new();
+        if args.masks.is_empty() {
+            // The default mask of attack mode 3 has more candidates than fit in
+            // the 64-bit keyspace of a feed.
+            return Err(format!("No mask specified.\n{}", USAGE));
+        }
+        if Path::new(&args.masks[0]).exists() {
+            // Each line of a mask file brings its own charsets, so those given
+            // on the command line don't apply, just like in hashcat.
+            args_cs = Default::default();
+            for file in &args.masks {
+                let buf = fs::read(file).map_err(|e| format!("{}: {}", file, e))?;
+                for line in buf.split(|&c| c == b'\n') {
+                    let line = line.strip_suffix(b"\r").unwrap_or(line);
+                    if line.is_empty() || line[0] == b'#' {
+                        continue;
+                    }
+                    let line = MaskLine::parse(line)?;
+                    masks.push((line.charsets, line.mask));
+                }
+            }
+        } else if args.masks.len() == 1 {
+            masks.push((Vec::new(), args.masks[0].as_bytes().to_vec()));
+        } else {
+            return Err(format!("{}: No such file or directory", args.masks[0]));
+        }
+
+        if !args.increment && (args.increment_min.is_some() || args.increment_max.is_some()) {
+            return Err(
+                "Increment-min and increment-max are only supported when combined with -i/--increment."
+                    .to_string(),
+            );
+        }
+        let increment_min = args.increment_min.unwrap_or(1);
+        let increment_max = args.increment_max.unwrap_or(PW_MAX);
+        if increment_min > increment_max {
+            return Err(
+                "Invalid --increment-min value specified - must be <= --increment-max.".to_string(),
+            );
+        }
+
+        let mut charsets = Charsets::default();
+        for (index, cs) in args_cs.iter().enumerate() {
+            if let Some(cs) = cs {
+                charsets.define(index, cs)?;
+            }
+        }
+
+        let mut entries = Vec::new();
+        let mut keyspace = 0u64;
+        for (line_cs, mask) in &masks {
+            let mut charsets = charsets.clone();
+            for (index, cs) in line_cs.iter().enumerate() {
+                charsets.define(index, cs)?;
+            }
+            let lens = if args.increment {
+                increment_min..=increment_max.min(mask_len(mask))
+            } else {
+                let len = mask_len(mask);
+                len..=len
+            };
+            for len in lens {
+                let mask = truncate_mask(mask, len);
+                let css = charsets.parse(mask)?;
+                let overflow = || {
+                    format!(
+                        "Integer overflow detected in keyspace of mask: {}",
+                        String::from_utf8_lossy(mask)
+                    )
+                };
+                let count = css
+                    .iter()
+                    .try_fold(1u64, |n, cs| n.checked_mul(cs.len() as u64))
+                    .ok_or_else(overflow)?;
+                entries.push(Entry {
+                    css,
+                    start: keyspace,
+                });
+                keyspace = keyspace.checked_add(count).ok_or_else(overflow)?;
+            }
+        }
+        if entries.is_empty() {
+            return Err("No mask left to process, check the increment range.".to_string());
+        }
+
+        Ok(Self { entries, keyspace })
+    }
+}
+
+/// Position of a generator thread: the mask and the index of the current
+/// character of each position.
+struct Cursor {
+    entry: usize,
+    digits: Vec<usize>,
+}
+
+impl Feed for Mask {
+    type ThreadState = Cursor;
+
+    const PLUGIN_OPTIONS: generic_plugin_options_t =
+        GENERIC_PLUGIN_OPTIONS_ICONV | GENERIC_PLUGIN_OPTIONS_RULES;
+
+    fn global_init(ctx: &GlobalContext) -> Result<Self, String> {
+        Self::new(&Args::parse(&ctx.args)?)
+    }
+
+    fn keyspace(&self) -> Option<u64> {
+        Some(self.keyspace)
+    }
+
+    fn thread_init(&self) -> Result<Cursor, String> {
+        Ok(Cursor {
+            entry: 0,
+            digits: vec![0; self.entries[0].css.len()],
+        })
+    }
+
+    fn seek(&self, cursor: &mut Cursor, offset: u64) -> Result<(), String> {
+        if offset >= self.keyspace {
+            cursor.entry = self.entries.len();
+            return Ok(());
+        }
+        cursor.entry = self.entries.partition_point(|e| e.start <= offset) - 1;
+        let css = &self.entries[cursor.entry].css;
+        let mut rem = offset - self.entries[cursor.entry].start;
+        cursor.digits.resize(css.len(), 0);
+        for (digit, cs) in cursor.digits.iter_mut().zip(css).rev() {
+            let radix = cs.len() as u64;
+            *digit = (rem % radix) as usize;
+            rem /= radix;
+        }
+        Ok(())
+    }
+
+    fn next(&self, cursor: &mut Cursor, out: &mut [u8; PW_MAX]) -> Result<Option<usize>, String> {
+        let Some(entry) = self.entries.get(cursor.entry) else {
+            return Ok(None);
+        };
+        let css = &entry.css;
+        for ((out, cs), &digit) in out.iter_mut().zip(css).zip(&cursor.digits) {
+            *out = cs[digit];
+        }
+
+        let mut carry = true;
+        for (digit, cs) in cursor.digits.iter_mut().zip(css).rev() {
+            *digit += 1;
+            if *digit < cs.len() {
+                carry = false;
+                break;
+            }
+            *digit = 0;
+        }
+        if carry {
+            cursor.entry += 1;
+            if let Some(next) = self.entries.get(cursor.entry) {
+                cursor.digits.clear();
+                cursor.digits.resize(next.css.len(), 0);
+            }
+        }
+
+        Ok(Some(css.len()))
+    }
+}
+
+export_feed!(Mask);
@@ -0,0 +1,199 @@
+/**
+ * Author......: See docs/credits.txt
+ * License.....: MIT
+ */
+use std::fs;
+
+use hashcat_feed::{
+    charset::{mask_len, truncate_mask, Charsets, MaskLine},
+    testing::{candidates, temp_file},
+    Feed,
+};
+
+use crate::{Args, Mask};
+
+const REPO: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../../..");
+
+fn mask(args: &[&str]) -> Result<Mask, String> {
+    let args: Vec<String> = args.iter().map(|s| s.to_string()).collect();
+    Mask::new(&Args::parse(&args)?)
+}
+
+#[test]
+fn test_builtin_charsets() {
+    let charsets = Charsets::default();
+    let css = charsets.parse(b"?l?u?d?s?a?b?h?H").unwrap();
+    let lens: Vec<usize> = css.iter().map(|cs| cs.len()).collect();
+    assert_eq!(lens, [26, 26, 10, 33, 95, 256, 16, 16]);
+    assert_eq!(css[3][..4], *b" !\"#");
+    assert_eq!(css[6], b"0123456789abcdef");
+
+    let css = charsets.parse(b"a??b").unwrap();
+    assert_eq!(css, [b"a".to_vec(), b"?".to_vec(), b"b".to_vec()]);
+
+    assert!(charsets.parse(b"?x").is_err());
+    assert!(charsets.parse(b"abc?").is_err());
+    assert!(charsets.parse(b"").is_err());
+    assert!(charsets.parse(&[b'a'; 257]).is_err());
+    assert_eq!(
+        charsets.parse(b"?1").unwrap_err(),
+        "Custom-charset 1 is undefined."
+    );
+}
+
+#[test]
+fn test_custom_charsets() {
+    let mut charsets = Charsets::default();
+    charsets.define(0, b"?dabca").unwrap();
+    charsets.define(1, b"?1?h").unwrap();
+    let css = charsets.parse(b"?1?2").unwrap();
+    assert_eq!(css[0], b"0123456789abc");
+    assert_eq!(css[1], b"0123456789abcdef");
+
+    let path = format!("{}/charsets/standard/German/de_cp1252.hcchr", REPO);
+    charsets.define(2, path.as_bytes()).unwrap();
+    let css = charsets.parse(b"?3").unwrap();
+    assert!(css[0].contains(&0xdf));
+    assert!(!css[0].contains(&b'\n'));
+
+    assert_eq!(
+        charsets.define(3, b"").unwrap_err(),
+        "Custom-charset 4 is empty."
+    );
+    assert!(mask(&["-1", "", "?1"]).is_err());
+}
+
+#[test]
+fn test_mask_line() {
+    let line = MaskLine::parse(b"?l?d,\\,.,?1?2?2").unwrap();
+    assert_eq!(line.charsets, [b"?l?d".to_vec(), b",.".to_vec()]);
+    assert_eq!(line.mask, b"?1?2?2");
+
+    let line = MaskLine::parse(b"pass\\,?d").unwrap();
+    assert!(line.charsets.is_empty());
+    assert_eq!(line.mask, b"pass,?d");
+
+    assert!(MaskLine::parse(b"1,2,3,4,5,6,7,8,?1").is_ok());
+    assert!(MaskLine::parse(b"1,2,3,4,5,6,7,8,9,?1").is_err());
+
+    assert_eq!(mask_len(b"a?l??b"), 4);
+    assert_eq!(truncate_mask(b"a?l??b", 2), b"a?l");
+    assert_eq!(truncate_mask(b"a?l??b", 9), b"a?l??b");
+}
+
+#[test]
+fn test_args() {
+    let args: Vec<String> = [
+        "-1",
+        "?l?d",
+        "--custom-charset2=xy",
+        "-i",
+        "--increment-max",
+        "3",
+    ]
+    .iter()
+    .chain(&["--", "-?1?2"])
+    .map(|s| s.to_string())
+    .collect();
+    let args = Args::parse(&args).unwrap();
+    assert_eq!(args.custom_charsets[0].as_deref(), Some(&b"?l?d"[..]));
+    assert_eq!(args.custom_charsets[1].as_deref(), Some(&b"xy"[..]));
+    assert!(args.increment);
+    assert_eq!(args.increment_max, Some(3));
+    assert_eq!(args.masks, ["-?1?2"]);
+
+    assert!(Args::parse(&["-9".to_string()]).is_err());
+    assert!(Args::parse(&["-1".to_string()]).is_err());
+    assert!(Args::parse(&["--increment-min=0".to_string()]).is_err());
+}
+
+#[test]
+fn test_order_and_keyspace() {
+    let feed = mask(&["-1", "ab", "?1x?d"]).unwrap();
+    assert_eq!(feed.keyspace(), Some(20));
+    let words = candidates(&feed, 0, 100);
+    assert_eq!(words.len(), 20);
+    assert_eq!(words[..3], ["ax0", "ax1", "ax2"]);
+    assert_eq!(words[19], "bx9");
+
+    assert!(mask(&["?1"]).is_err());
+    assert!(mask(&["?b?b?b?b?b?b?b?b?b"]).is_err());
+    assert_eq!(
+        mask(&["?b?b?b?b?b?b?b?d"]).unwrap().keyspace(),
+        Some(10 << 56)
+    );
+}
+
+#[test]
+fn test_increment() {
+    let feed = mask(&["-i", "?d?l?d"]).unwrap();
+    assert_eq!(feed.keyspace(), Some(10 + 260 + 2600));
+    let words = candidates(&feed, 9, 3);
+    assert_eq!(words, ["9", "0a", "0b"]);
+
+    let feed = mask(&["-i", "--increment-min=2", "--increment-max=2", "?d?l?d"]).unwrap();
+    assert_eq!(feed.keyspace(), Some(260));
+
+    assert!(mask(&["--increment-min=2", "?d?d"]).is_err());
+    assert!(mask(&["-i", "--increment-min=3", "?d?d"]).is_err());
+    assert!(mask(&["-i", "--increment-min=3", "--increment-max=2", "?d?d?d"]).is_err());
+}
+
+#[test]
+fn test_seek() {
+    let feed = mask(&["-i", "-1", "?d?s", "-2", "xyz", "?2?1?l?2"]).unwrap();
+    let keyspace = feed.keyspace().unwrap();
+    let all = candidates(&feed, 0, keyspace as usize + 1);
+    assert_eq!(all.len() as u64, keyspace);
+    for offset in (0..keyspace).step_by(97).chain([keyspace - 1]) {
+        assert_eq!(
+            candidates(&feed, offset, 3),
+            all[offset as usize..][..3.min((keyspace - offset) as usize)]
+        );
+    }
+    assert!(candidates(&feed, keyspace, 1).is_empty());
+    assert!(candidates(&feed, u64::MAX, 1).is_empty());
+
+    let feed = mask(&["?b?b?b?b?b?b?b"]).unwrap();
+    let offset = 0x0001_0203_0405_0607_u64;
+    let word = candidates(&feed, offset, 1);
+    assert_eq!(word[0].as_bytes(), &offset.to_be_bytes()[1..]);
+}
+
+#[test]
+fn test_mask_files() {
+    assert!(mask(&[]).is_err());
+    let path = format!("{}/masks/hashcat-default.hcmask", REPO);
+    let file = mask(&["-i", "--increment-max=8", &path]).unwrap();
+    let args = [
+        "-1",
+        "?l?d?u",
+        "-2",
+        "?l?d",
+        "-3",
+        "?l?d*!$@_",
+        "-i",
+        "--increment-max=8",
+    ];
+    let args: Vec<&str> = args.iter().copied().chain(["?1?2?2?2?2?2?2?3"]).collect();
+    let line = mask(&args).unwrap();
+    assert_eq!(file.keyspace(), line.keyspace());
+    assert_eq!(candidates(&file, 12345, 5), candidates(&line, 12345, 5));
+
+    // Charsets of the command line don't apply to mask files.
+    let path = format!("{}/masks/rockyou-1-60.hcmask", REPO);
+    let feed = mask(&["-1", "x", &path]).unwrap();
+    assert_eq!(feed.entries.len(), 837);
+    assert_eq!(candidates(&feed, 0, 1), ["0"]);
+    assert_eq!(candidates(&feed, 10, 1), ["00"]);
+
+    let feed = mask(&[&path, &path]).unwrap();
+    assert_eq!(feed.entries.len(), 2 * 837);
+
+    let path = temp_file("empty.hcmask", ",?1\n");
+    assert_eq!(
+        mask(&[path.to_str().unwrap()]).err().unwrap(),
+        "Custom-charset 1 is empty."
+    );
+    fs::remove_file(path).unwrap();
+}
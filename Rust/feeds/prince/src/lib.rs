@@ -0,0 +1,339 @@
+/**
+ * Author......: See docs/credits.txt
+ * License.....: MIT
+ */
+use std::collections::HashSet;
+
+use hashcat_feed::{
+    event, export_feed, generic_plugin_options_t, Feed, GlobalContext, Wordlist,
+    GENERIC_PLUGIN_OPTIONS_ICONV, GENERIC_PLUGIN_OPTIONS_RULES, PW_MAX,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// The longest element and candidate, as in princeprocessor.
+const LEN_MAX: usize = 32;
+
+const USAGE: &str = "usage: rust_prince.so [--pw-min=N] [--pw-max=N] [--elem-cnt-min=N] \
+                     [--elem-cnt-max=N] [--wl-max=N] [--dupe-check-disable] [--case-permute] \
+                     WORDLIST";
+
+/// The command line of the feed, following the princeprocessor options of the
+/// same name.
+#[derive(Debug, PartialEq, Eq)]
+struct Args {
+    pw_min: usize,
+    pw_max: usize,
+    elem_cnt_min: usize,
+    elem_cnt_max: usize,
+    wl_max: usize,
+    dupe_check: bool,
+    case_permute: bool,
+    wordlist: Option<String>,
+}
+
+impl Default for Args {
+    fn default() -> Self {
+        Self {
+            pw_min: 1,
+            pw_max: 16,
+            elem_cnt_min: 1,
+            elem_cnt_max: 8,
+            wl_max: 10_000_000,
+            dupe_check: true,
+            case_permute: false,
+            wordlist: None,
+        }
+    }
+}
+
+impl Args {
+    fn parse(args: &[String]) -> Result<Self, String> {
+        let mut parsed = Self::default();
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            if !arg.starts_with("--") {
+                if parsed.wordlist.is_some() {
+                    return Err(format!("Unexpected argument {}.\n{}", arg, USAGE));
+                }
+                parsed.wordlist = Some(arg.clone());
+                continue;
+            }
+            match arg.as_str() {
+                "--dupe-check-disable" => parsed.dupe_check = false,
+                "--case-permute" => parsed.case_permute = true,
+                _ => {
+                    let (name, value) = match arg.split_once('=') {
+                        Some((name, value)) => (name, value.to_string()),
+                        None => (
+                            arg.as_str(),
+                            iter.next().cloned().ok_or_else(|| {
+                                format!("Option {} requires a value.\n{}", arg, USAGE)
+                            })?,
+                        ),
+                    };
+                    let field = match name {
+                        "--pw-min" => &mut parsed.pw_min,
+                        "--pw-max" => &mut parsed.pw_max,
+                        "--elem-cnt-min" => &mut parsed.elem_cnt_min,
+                        "--elem-cnt-max" => &mut parsed.elem_cnt_max,
+                        "--wl-max" => &mut parsed.wl_max,
+                        _ => return Err(format!("Unknown option {}.\n{}", arg, USAGE)),
+                    };
+                    *field = match value.parse() {
+                        Ok(n) if n > 0 => n,
+                        _ => return Err(format!("Invalid {} value specified.", name)),
+                    };
+                }
+            }
+        }
+
+        if parsed.pw_max > LEN_MAX {
+            return Err(format!("Value of --pw-max must be {} or less.", LEN_MAX));
+        }
+        if parsed.pw_min > parsed.pw_max {
+            return Err("Value of --pw-min must be smaller or equal to --pw-max.".to_string());
+        }
+        if parsed.elem_cnt_min > parsed.elem_cnt_max {
+            return Err(
+                "Value of --elem-cnt-min must be smaller or equal to --elem-cnt-max.".to_string(),
+            );
+        }
+        Ok(parsed)
+    }
+}
+
+/// The elements of one length, stored back to back.
+#[derive(Debug, Default)]
+struct Elems {
+    data: Vec<u8>,
+    cnt: u64,
+}
+
+/// A sequence of element lengths, e.g. `[4, 2]` for any element of length 4
+/// followed by any element of length 2.
+#[derive(Debug)]
+struct Chain {
+    lens: Vec<u8>,
+    /// Offset of the first candidate of this chain in the whole keyspace.
+    start: u64,
+}
+
+/// Generates the candidates of the PRINCE attack: chains of elements from a
+/// wordlist, grouped by length.
+///
+/// All chains of the allowed lengths and element counts are generated and
+/// sorted by their keyspace, smallest first, so candidates made of few and
+/// frequent elements come early. Unlike princeprocessor, which walks the
+/// candidate lengths in turns, the chains are processed one after the other,
+/// so any offset maps to a chain and a position in it.
+///
+/// The words of the wordlist are assumed to be sorted by probability. Within
+/// a chain the first element changes fastest.
+struct Prince {
+    by_len: Vec<Elems>,
+    chains: Vec<Chain>,
+    keyspace: u64,
+}
+
+impl Prince {
+    fn new(args: &Args) -> Result<Self, String> {
+        let path = args
+            .wordlist
+            .as_ref()
+            .ok_or_else(|| format!("No wordlist specified.\n{}", USAGE))?;
+        let wordlist = Wordlist::load(path)?;
+
+        let mut by_len: Vec<Elems> = (0..=args.pw_max).map(|_| Elems::default()).collect();
+        let mut seen = HashSet::new();
+        let mut words_cnt = 0;
+        let mut add = |word: &[u8]| {
+            if word.is_empty() || word.len() > args.pw_max || words_cnt == args.wl_max {
+                return;
+            }
+            if args.dupe_check && !seen.insert(word.to_vec()) {
+                return;
+            }
+            let elems = &mut by_len[word.len()];
+            elems.data.extend_from_slice(word);
+            elems.cnt += 1;
+            words_cnt += 1;
+        };
+        for word in wordlist.iter() {
+            add(&word);
+            if args.case_permute && word.first().is_some_and(u8::is_ascii_alphabetic) {
+                let mut word = word.into_owned();
+                word[0] ^= 0x20;
+                add(&word);
+            }
+        }
+        if words_cnt == 0 {
+            return Err(format!("{}: empty file.", path));
+        }
+
+        let mut chains = Vec::new();
+        for pw_len in args.pw_min..=args.pw_max {
+            add_chains(&by_len, args, pw_len, &mut Vec::new(), &mut chains);
+        }
+        // Chains with a keyspace beyond 64 bits go last and are cut off below.
+        let mut chains: Vec<(Option<u64>, usize, Vec<u8>)> = chains
+            .into_iter()
+            .map(|lens: Vec<u8>| {
+                let ks = lens
+                    .iter()
+                    .try_fold(1u64, |n, &len| n.checked_mul(by_len[len as usize].cnt));
+                let pw_len = lens.iter().map(|&len| len as usize).sum();
+                (ks, pw_len, lens)
+            })
+            .collect();
+        chains.sort_by_key(|&(ks, pw_len, _)| (ks.is_none(), ks, pw_len));
+
+        let mut keyspace = 0u64;
+        let mut starts = Vec::with_capacity(chains.len());
+        for (ks, _, _) in &chains {
+            let Some(end) = ks.and_then(|ks| keyspace.checked_add(ks)) else {
+                break;
+            };
+            starts.push(keyspace);
+            keyspace = end;
+        }
+        if starts.len() < chains.len() {
+            event::warning(&format!(
+                "The keyspace exceeds 64 bits, only the first {} of {} chains are used.",
+                starts.len(),
+                chains.len()
+            ));
+        }
+        let chains: Vec<Chain> = chains
+            .into_iter()
+            .zip(starts)
+            .map(|((_, _, lens), start)| Chain { lens, start })
+            .collect();
+        if chains.is_empty() {
+            return Err(
+                "No chains left to process, check the password length and element count ranges."
+                    .to_string(),
+            );
+        }
+
+        Ok(Self {
+            by_len,
+            chains,
+            keyspace,
+        })
+    }
+
+    fn elem(&self, len: u8, index: u64) -> &[u8] {
+        let len = len as usize;
+        let start = index as usize * len;
+        &self.by_len[len].data[start..start + len]
+    }
+}
+
+/// Adds all chains adding up to `pw_len` that start with `lens` and have an
+/// allowed number of elements, trying shorter elements first.
+fn add_chains(
+    by_len: &[Elems],
+    args: &Args,
+    pw_len: usize,
+    lens: &mut Vec<u8>,
+    chains: &mut Vec<Vec<u8>>,
+) {
+    if pw_len == 0 {
+        if lens.len() >= args.elem_cnt_min {
+            chains.push(lens.clone());
+        }
+        return;
+    }
+    if lens.len() == args.elem_cnt_max {
+        return;
+    }
+    for len in 1..=pw_len {
+        if by_len[len].cnt == 0 {
+            continue;
+        }
+        lens.push(len as u8);
+        add_chains(by_len, args, pw_len - len, lens, chains);
+        lens.pop();
+    }
+}
+
+/// Position of a generator thread: the chain and the index of the current
+/// element of each of its positions.
+struct Cursor {
+    chain: usize,
+    digits: Vec<u64>,
+}
+
+impl Feed for Prince {
+    type ThreadState = Cursor;
+
+    const PLUGIN_OPTIONS: generic_plugin_options_t =
+        GENERIC_PLUGIN_OPTIONS_ICONV | GENERIC_PLUGIN_OPTIONS_RULES;
+
+    fn global_init(ctx: &GlobalContext) -> Result<Self, String> {
+        Self::new(&Args::parse(&ctx.args)?)
+    }
+
+    fn keyspace(&self) -> Option<u64> {
+        Some(self.keyspace)
+    }
+
+    fn thread_init(&self) -> Result<Cursor, String> {
+        Ok(Cursor {
+            chain: 0,
+            digits: vec![0; self.chains[0].lens.len()],
+        })
+    }
+
+    fn seek(&self, cursor: &mut Cursor, offset: u64) -> Result<(), String> {
+        if offset >= self.keyspace {
+            cursor.chain = self.chains.len();
+            return Ok(());
+        }
+        cursor.chain = self.chains.partition_point(|c| c.start <= offset) - 1;
+        let chain = &self.chains[cursor.chain];
+        let mut rem = offset - chain.start;
+        cursor.digits.resize(chain.lens.len(), 0);
+        for (digit, &len) in cursor.digits.iter_mut().zip(&chain.lens) {
+            let radix = self.by_len[len as usize].cnt;
+            *digit = rem % radix;
+            rem /= radix;
+        }
+        Ok(())
+    }
+
+    fn next(&self, cursor: &mut Cursor, out: &mut [u8; PW_MAX]) -> Result<Option<usize>, String> {
+        let Some(chain) = self.chains.get(cursor.chain) else {
+            return Ok(None);
+        };
+        let mut len = 0;
+        for (&elem_len, &digit) in chain.lens.iter().zip(&cursor.digits) {
+            let elem = self.elem(elem_len, digit);
+            out[len..len + elem.len()].copy_from_slice(elem);
+            len += elem.len();
+        }
+
+        let mut carry = true;
+        for (digit, &elem_len) in cursor.digits.iter_mut().zip(&chain.lens) {
+            *digit += 1;
+            if *digit < self.by_len[elem_len as usize].cnt {
+                carry = false;
+                break;
+            }
+            *digit = 0;
+        }
+        if carry {
+            cursor.chain += 1;
+            if let Some(next) = self.chains.get(cursor.chain) {
+                cursor.digits.clear();
+                cursor.digits.resize(next.lens.len(), 0);
+            }
+        }
+
+        Ok(Some(len))
+    }
+}
+
+export_feed!(Prince);
@@ -0,0 +1,114 @@
+/**
+ * Author......: See docs/credits.txt
+ * License.....: MIT
+ */
+use std::fs;
+
+use hashcat_feed::{
+    testing::{candidates, temp_file},
+    Feed,
+};
+
+use crate::{Args, Prince};
+
+const REPO: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../../..");
+
+fn prince(args: &[&str]) -> Result<Prince, String> {
+    let args: Vec<String> = args.iter().map(|s| s.to_string()).collect();
+    Prince::new(&Args::parse(&args)?)
+}
+
+#[test]
+fn test_args() {
+    let args = |args: &[&str]| {
+        let args: Vec<String> = args.iter().map(|s| s.to_string()).collect();
+        Args::parse(&args)
+    };
+    let parsed = args(&["--pw-min=4", "--pw-max", "12", "--case-permute", "words"]).unwrap();
+    assert_eq!((parsed.pw_min, parsed.pw_max), (4, 12));
+    assert!(parsed.case_permute && parsed.dupe_check);
+    assert_eq!(parsed.wordlist.as_deref(), Some("words"));
+
+    assert!(args(&["--pw-max=33"]).is_err());
+    assert!(args(&["--pw-min=5", "--pw-max=4"]).is_err());
+    assert!(args(&["--elem-cnt-min=3", "--elem-cnt-max=2"]).is_err());
+    assert!(args(&["--pw-min=0"]).is_err());
+    assert!(args(&["--pw-min"]).is_err());
+    assert!(args(&["--limit=1"]).is_err());
+    assert!(args(&["a", "b"]).is_err());
+}
+
+#[test]
+fn test_chains() {
+    let words = temp_file("chains", "a\nb\ncd\nb\n");
+    let words = words.to_str().unwrap();
+
+    // Chains sorted by keyspace: [2], [1], [1, 2], [2, 1], [1, 1], [1, 1, 1].
+    let feed = prince(&["--pw-max=3", words]).unwrap();
+    assert_eq!(feed.keyspace(), Some(1 + 2 + 2 + 2 + 4 + 8));
+    assert_eq!(
+        candidates(&feed, 0, 12),
+        ["cd", "a", "b", "acd", "bcd", "cda", "cdb", "aa", "ba", "ab", "bb", "aaa"]
+    );
+
+    let feed = prince(&["--pw-min=3", "--pw-max=3", "--elem-cnt-max=2", words]).unwrap();
+    assert_eq!(candidates(&feed, 0, 10), ["acd", "bcd", "cda", "cdb"]);
+
+    let feed = prince(&["--pw-max=3", "--elem-cnt-min=3", words]).unwrap();
+    assert_eq!(feed.keyspace(), Some(8));
+
+    let feed = prince(&["--pw-max=2", "--dupe-check-disable", words]).unwrap();
+    assert_eq!(feed.keyspace(), Some(1 + 3 + 9));
+
+    let feed = prince(&["--pw-max=1", "--wl-max=1", words]).unwrap();
+    assert_eq!(candidates(&feed, 0, 10), ["a"]);
+
+    let feed = prince(&["--pw-max=1", "--case-permute", words]).unwrap();
+    assert_eq!(candidates(&feed, 0, 10), ["a", "A", "b", "B"]);
+
+    assert!(prince(&["--pw-min=4", "--pw-max=4", "--elem-cnt-max=1", words]).is_err());
+    assert!(prince(&[]).is_err());
+    let empty = temp_file("empty", "\n\n");
+    assert!(prince(&[empty.to_str().unwrap()]).is_err());
+
+    fs::remove_file(words).unwrap();
+    fs::remove_file(empty).unwrap();
+}
+
+#[test]
+fn test_seek() {
+    let words = temp_file("seek", "1\n2\n3\nab\ncd\nxyz\n");
+    let words = words.to_str().unwrap();
+    let feed = prince(&["--pw-max=6", "--elem-cnt-max=4", words]).unwrap();
+    let keyspace = feed.keyspace().unwrap();
+
+    let all = candidates(&feed, 0, keyspace as usize + 1);
+    assert_eq!(all.len() as u64, keyspace);
+    assert!(all.iter().all(|word| (1..=6).contains(&word.len())));
+    for offset in [1, 2, 3, 10, 100, 500, keyspace - 1] {
+        assert_eq!(
+            candidates(&feed, offset, 3),
+            all[offset as usize..]
+                .iter()
+                .take(3)
+                .cloned()
+                .collect::<Vec<_>>()
+        );
+    }
+    assert!(candidates(&feed, keyspace, 1).is_empty());
+
+    fs::remove_file(words).unwrap();
+}
+
+#[test]
+fn test_example_dict() {
+    // The default chains of example.dict exceed 64 bits, the largest are
+    // dropped.
+    let feed = prince(&[&format!("{}/example.dict", REPO)]).unwrap();
+    let keyspace = feed.keyspace().unwrap();
+    assert!(keyspace > u64::MAX / 2);
+    assert_eq!(candidates(&feed, 0, 1), ["0"]);
+    let seq = candidates(&feed, keyspace - 3, 5);
+    assert_eq!(seq.len(), 3);
+    assert_eq!(candidates(&feed, keyspace - 2, 5), seq[1..]);
+}
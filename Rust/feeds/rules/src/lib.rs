@@ -0,0 +1,178 @@
+/**
+ * Author......: See docs/credits.txt
+ * License.....: MIT
+ */
+use std::fs;
+
+use hashcat_feed::{
+    event, export_feed, generic_plugin_options_t, Feed, GlobalContext, Wordlist,
+    GENERIC_PLUGIN_OPTIONS_ICONV, GENERIC_PLUGIN_OPTIONS_RULES, PW_MAX, REJECTED,
+};
+
+use crate::rule::{Rule, RP_PASSWORD_SIZE};
+
+mod rule;
+
+#[cfg(test)]
+mod tests;
+
+const USAGE: &str = "usage: rust_rules.so WORDLIST -r RULES_FILE [-r RULES_FILE...]";
+
+/// Loads the valid rules of a rule file, skipping empty lines and comments.
+/// Invalid rules are skipped with a warning, like hashcat does.
+fn load_rules(path: &str) -> Result<Vec<Rule>, String> {
+    let buf = fs::read(path).map_err(|e| format!("{}: {}", path, e))?;
+    let mut rules = Vec::new();
+    for (line_nr, line) in buf.split(|&c| c == b'\n').enumerate() {
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        if line.is_empty() || line[0] == b'#' {
+            continue;
+        }
+        match Rule::parse(line) {
+            Ok(rule) => rules.push(rule),
+            Err(e) => event::warning(&format!(
+                "Skipping invalid or unsupported rule in file {} on line {}: {} ({})",
+                path,
+                line_nr + 1,
+                String::from_utf8_lossy(line),
+                e
+            )),
+        }
+    }
+    Ok(rules)
+}
+
+/// Applies the rules of one or more rule files to each word of a wordlist,
+/// like attack mode 0 with `-r`, but on the host and with the memory and
+/// reject functions of `-j` and `-k` available.
+///
+/// Candidate `i` is rule `i % rules` applied to word `i / rules`, so all rules
+/// are applied to a word before moving on to the next. With several rule
+/// files, each rule of the first file is combined with each of the second and
+/// so on, the first file changing fastest.
+///
+/// A candidate rejected by a rule, or a word longer than a rule can take, is
+/// returned as [`REJECTED`] at its own offset, so batches stay in step with
+/// the keyspace and hashcat counts it like its own rejects.
+struct Rules {
+    wordlist: Wordlist,
+    files: Vec<Vec<Rule>>,
+    rules_cnt: u64,
+    keyspace: u64,
+}
+
+impl Rules {
+    fn new(args: &[String]) -> Result<Self, String> {
+        let mut wordlist = None;
+        let mut files = Vec::new();
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            let file = match arg.as_str() {
+                "-r" | "--rules-file" => iter
+                    .next()
+                    .ok_or_else(|| format!("Option {} requires a value.\n{}", arg, USAGE))?,
+                _ => match arg.strip_prefix("--rules-file=") {
+                    Some(file) => file,
+                    None if wordlist.is_none() => {
+                        wordlist = Some(arg);
+                        continue;
+                    }
+                    None => return Err(format!("Unexpected argument {}.\n{}", arg, USAGE)),
+                },
+            };
+            files.push(load_rules(file)?);
+        }
+        let wordlist = wordlist.ok_or_else(|| format!("No wordlist specified.\n{}", USAGE))?;
+        if files.is_empty() {
+            return Err(format!("No rules file specified.\n{}", USAGE));
+        }
+
+        let rules_cnt = files
+            .iter()
+            .try_fold(1u64, |n, rules| n.checked_mul(rules.len() as u64))
+            .ok_or("Unsupported number of rules used in rule chaining.")?;
+        if rules_cnt == 0 {
+            return Err("No valid rules left.".to_string());
+        }
+
+        let wordlist = Wordlist::load(wordlist)?;
+        let keyspace = (wordlist.len() as u64)
+            .checked_mul(rules_cnt)
+            .ok_or("Integer overflow detected in keyspace of wordlist and rules.")?;
+
+        Ok(Self {
+            wordlist,
+            files,
+            rules_cnt,
+            keyspace,
+        })
+    }
+
+    /// The rules chained together into rule `index`.
+    fn rule(&self, mut index: u64) -> impl Iterator<Item = &Rule> {
+        self.files.iter().map(move |rules| {
+            let cnt = rules.len() as u64;
+            let rule = &rules[(index % cnt) as usize];
+            index /= cnt;
+            rule
+        })
+    }
+}
+
+/// Position of a generator thread and the word the rules are applied to.
+struct Cursor {
+    offset: u64,
+    word: Vec<u8>,
+}
+
+impl Feed for Rules {
+    type ThreadState = Cursor;
+
+    const PLUGIN_OPTIONS: generic_plugin_options_t =
+        GENERIC_PLUGIN_OPTIONS_ICONV | GENERIC_PLUGIN_OPTIONS_RULES;
+
+    fn global_init(ctx: &GlobalContext) -> Result<Self, String> {
+        Self::new(&ctx.args)
+    }
+
+    fn keyspace(&self) -> Option<u64> {
+        Some(self.keyspace)
+    }
+
+    fn thread_init(&self) -> Result<Cursor, String> {
+        Ok(Cursor {
+            offset: 0,
+            word: Vec::with_capacity(RP_PASSWORD_SIZE),
+        })
+    }
+
+    fn seek(&self, cursor: &mut Cursor, offset: u64) -> Result<(), String> {
+        cursor.offset = offset;
+        Ok(())
+    }
+
+    fn next(&self, cursor: &mut Cursor, out: &mut [u8; PW_MAX]) -> Result<Option<usize>, String> {
+        if cursor.offset >= self.keyspace {
+            return Ok(None);
+        }
+        let word = (cursor.offset / self.rules_cnt) as usize;
+        let rule = cursor.offset % self.rules_cnt;
+        cursor.offset += 1;
+
+        let word = self.wordlist.word(word);
+        if word.len() > RP_PASSWORD_SIZE {
+            return Ok(Some(REJECTED));
+        }
+        cursor.word.clear();
+        cursor.word.extend_from_slice(&word);
+        if !Rule::apply_chain(self.rule(rule), &mut cursor.word) {
+            return Ok(Some(REJECTED));
+        }
+
+        let len = cursor.word.len().min(PW_MAX);
+        out[..len].copy_from_slice(&cursor.word[..len]);
+        Ok(Some(len))
+    }
+}
+
+export_feed!(Rules);
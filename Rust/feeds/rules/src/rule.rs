@@ -0,0 +1,653 @@
+/**
+ * Author......: See docs/credits.txt
+ * License.....: MIT
+ */
+use std::mem;
+
+/// Size of the rule engine buffer. As in hashcat, a function that would grow
+/// the word to this length leaves it unchanged instead.
+pub const RP_PASSWORD_SIZE: usize = 256;
+
+/// A position or length argument: `0-9` and `A-Z`, or `p` for the position
+/// of the character last found by `/` or `%`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Pos {
+    Num(usize),
+    Found,
+}
+
+/// The character argument of the functions that also accept a class with
+/// `~`, e.g. `s` and `~s?l`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Class {
+    Char(u8),
+    Lower,
+    Upper,
+    Digit,
+    LowerHex,
+    UpperHex,
+    Sym,
+}
+
+impl Class {
+    fn matches(self, c: u8) -> bool {
+        match self {
+            Class::Char(x) => c == x,
+            Class::Lower => c.is_ascii_lowercase(),
+            Class::Upper => c.is_ascii_uppercase(),
+            Class::Digit => c.is_ascii_digit(),
+            Class::LowerHex => c.is_ascii_digit() || (b'a'..=b'f').contains(&c),
+            Class::UpperHex => c.is_ascii_digit() || (b'A'..=b'F').contains(&c),
+            Class::Sym => c.is_ascii_punctuation() || c == b' ',
+        }
+    }
+}
+
+/// One function of a rule, see docs/rules.txt.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Op {
+    Noop,
+    Lower,
+    Upper,
+    Capitalize,
+    InvertCapitalize,
+    ToggleAll,
+    ToggleAt(Pos),
+    ToggleAtSep(Pos, u8),
+    Reverse,
+    Duplicate,
+    DuplicateTimes(Pos),
+    Reflect,
+    RotateLeft,
+    RotateRight,
+    Append(u8),
+    Prepend(u8),
+    DeleteFirst,
+    DeleteLast,
+    DeleteAt(Pos),
+    Extract(Pos, Pos),
+    Omit(Pos, Pos),
+    Insert(Pos, u8),
+    Overstrike(Pos, u8),
+    TruncateAt(Pos),
+    Replace(Class, u8),
+    Purge(Class),
+    DupeFirstChar(Pos),
+    DupeLastChar(Pos),
+    DupeAllChars,
+    DupeBlockFirst(Pos),
+    DupeBlockLast(Pos),
+    SwapFirst,
+    SwapLast,
+    SwapAt(Pos, Pos),
+    ShiftLeft(Pos),
+    ShiftRight(Pos),
+    Increment(Pos),
+    Decrement(Pos),
+    ReplaceNext(Pos),
+    ReplacePrev(Pos),
+    Title(Class),
+    ToHexLower,
+    ToHexUpper,
+    ExtractMemory(Pos, Pos, Pos),
+    AppendMemory,
+    PrependMemory,
+    Memorize,
+    RejectLonger(Pos),
+    RejectShorter(Pos),
+    RejectNotLength(Pos),
+    RejectContain(Class),
+    RejectNotContain(Class),
+    RejectNotFirst(Class),
+    RejectNotLast(Class),
+    RejectNotAt(Pos, Class),
+    RejectFewer(Pos, Class),
+    RejectUnchanged,
+}
+
+/// Replaces the `\xHH` notation by the byte it stands for.
+fn decode_hex_notation(buf: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(buf.len());
+    let mut pos = 0;
+    while pos < buf.len() {
+        if let [b'\\', b'x', hi, lo, ..] = buf[pos..]
+            && let (Some(hi), Some(lo)) = ((hi as char).to_digit(16), (lo as char).to_digit(16))
+        {
+            out.push((hi << 4 | lo) as u8);
+            pos += 4;
+            continue;
+        }
+        out.push(buf[pos]);
+        pos += 1;
+    }
+    out
+}
+
+struct Parser<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn byte(&mut self) -> Option<u8> {
+        let c = *self.buf.get(self.pos)?;
+        self.pos += 1;
+        Some(c)
+    }
+
+    fn pos(&mut self) -> Option<Pos> {
+        match self.byte()? {
+            c @ b'0'..=b'9' => Some(Pos::Num((c - b'0') as usize)),
+            c @ b'A'..=b'Z' => Some(Pos::Num((c - b'A') as usize + 10)),
+            b'p' => Some(Pos::Found),
+            _ => None,
+        }
+    }
+
+    /// The `?C` argument of the class based functions.
+    fn class(&mut self) -> Option<Class> {
+        if self.byte()? != b'?' {
+            return None;
+        }
+        match self.byte()? {
+            b'?' => Some(Class::Char(b'?')),
+            b'l' => Some(Class::Lower),
+            b'u' => Some(Class::Upper),
+            b'd' => Some(Class::Digit),
+            b'h' => Some(Class::LowerHex),
+            b'H' => Some(Class::UpperHex),
+            b's' => Some(Class::Sym),
+            _ => None,
+        }
+    }
+
+    fn char_class(&mut self) -> Option<Class> {
+        self.byte().map(Class::Char)
+    }
+
+    fn op(&mut self) -> Option<Op> {
+        let op = match self.byte()? {
+            b':' | b' ' => Op::Noop,
+            b'l' => Op::Lower,
+            b'u' => Op::Upper,
+            b'c' => Op::Capitalize,
+            b'C' => Op::InvertCapitalize,
+            b't' => Op::ToggleAll,
+            b'T' => Op::ToggleAt(self.pos()?),
+            b'3' => Op::ToggleAtSep(self.pos()?, self.byte()?),
+            b'r' => Op::Reverse,
+            b'd' => Op::Duplicate,
+            b'p' => Op::DuplicateTimes(self.pos()?),
+            b'f' => Op::Reflect,
+            b'{' => Op::RotateLeft,
+            b'}' => Op::RotateRight,
+            b'$' => Op::Append(self.byte()?),
+            b'^' => Op::Prepend(self.byte()?),
+            b'[' => Op::DeleteFirst,
+            b']' => Op::DeleteLast,
+            b'D' => Op::DeleteAt(self.pos()?),
+            b'x' => Op::Extract(self.pos()?, self.pos()?),
+            b'O' => Op::Omit(self.pos()?, self.pos()?),
+            b'i' => Op::Insert(self.pos()?, self.byte()?),
+            b'o' => Op::Overstrike(self.pos()?, self.byte()?),
+            b'\'' => Op::TruncateAt(self.pos()?),
+            b's' => Op::Replace(self.char_class()?, self.byte()?),
+            b'@' => Op::Purge(self.char_class()?),
+            b'z' => Op::DupeFirstChar(self.pos()?),
+            b'Z' => Op::DupeLastChar(self.pos()?),
+            b'q' => Op::DupeAllChars,
+            b'y' => Op::DupeBlockFirst(self.pos()?),
+            b'Y' => Op::DupeBlockLast(self.pos()?),
+            b'k' => Op::SwapFirst,
+            b'K' => Op::SwapLast,
+            b'*' => Op::SwapAt(self.pos()?, self.pos()?),
+            b'L' => Op::ShiftLeft(self.pos()?),
+            b'R' => Op::ShiftRight(self.pos()?),
+            b'+' => Op::Increment(self.pos()?),
+            b'-' => Op::Decrement(self.pos()?),
+            b'.' => Op::ReplaceNext(self.pos()?),
+            b',' => Op::ReplacePrev(self.pos()?),
+            b'e' => Op::Title(self.char_class()?),
+            b'E' => Op::Title(Class::Char(b' ')),
+            b'h' => Op::ToHexLower,
+            b'H' => Op::ToHexUpper,
+            b'X' => {
+                let (pos, len, at) = (self.pos()?, self.pos()?, self.pos()?);
+                if len == Pos::Num(0) {
+                    return None;
+                }
+                Op::ExtractMemory(pos, len, at)
+            }
+            b'4' => Op::AppendMemory,
+            b'6' => Op::PrependMemory,
+            b'M' => Op::Memorize,
+            b'<' => Op::RejectLonger(self.pos()?),
+            b'>' => Op::RejectShorter(self.pos()?),
+            b'_' => Op::RejectNotLength(self.pos()?),
+            b'!' => Op::RejectContain(self.char_class()?),
+            b'/' => Op::RejectNotContain(self.char_class()?),
+            b'(' => Op::RejectNotFirst(self.char_class()?),
+            b')' => Op::RejectNotLast(self.char_class()?),
+            b'=' => Op::RejectNotAt(self.pos()?, self.char_class()?),
+            b'%' => Op::RejectFewer(self.pos()?, self.char_class()?),
+            b'Q' => Op::RejectUnchanged,
+            b'~' => match self.byte()? {
+                b's' => Op::Replace(self.class()?, self.byte()?),
+                b'@' => Op::Purge(self.class()?),
+                b'e' => Op::Title(self.class()?),
+                b'!' => Op::RejectContain(self.class()?),
+                b'/' => Op::RejectNotContain(self.class()?),
+                b'(' => Op::RejectNotFirst(self.class()?),
+                b')' => Op::RejectNotLast(self.class()?),
+                b'=' => Op::RejectNotAt(self.pos()?, self.class()?),
+                b'%' => Op::RejectFewer(self.pos()?, self.class()?),
+                _ => return None,
+            },
+            _ => return None,
+        };
+        Some(op)
+    }
+}
+
+/// A rule as applied by hashcat with `-j` and `-k`, which unlike the rules
+/// running on the compute devices supports the memory and reject functions.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Rule {
+    ops: Vec<Op>,
+}
+
+/// State of one application of a rule.
+struct Run<'a> {
+    word: &'a mut Vec<u8>,
+    mem: Vec<u8>,
+    found: Option<usize>,
+}
+
+impl Run<'_> {
+    fn find(&mut self, class: Class) -> bool {
+        let found = self.word.iter().position(|&c| class.matches(c));
+        if found.is_some() {
+            self.found = found;
+        }
+        found.is_some()
+    }
+
+    fn count_at_least(&mut self, class: Class, n: usize) -> bool {
+        let mut cnt = 0;
+        for (pos, &c) in self.word.iter().enumerate() {
+            if cnt == n {
+                break;
+            }
+            if class.matches(c) {
+                cnt += 1;
+                self.found = Some(pos);
+            }
+        }
+        cnt >= n
+    }
+
+    /// Applies `op`, returns `None` if the word is rejected.
+    fn apply(&mut self, op: &Op) -> Option<()> {
+        let found = self.found;
+        let at = |pos: Pos| match pos {
+            Pos::Num(n) => Some(n),
+            Pos::Found => found,
+        };
+        let word = &mut *self.word;
+        let len = word.len();
+        let fits = |add: usize| len + add < RP_PASSWORD_SIZE;
+        match *op {
+            Op::Noop => {}
+            Op::Lower => word.make_ascii_lowercase(),
+            Op::Upper => word.make_ascii_uppercase(),
+            Op::Capitalize => {
+                word.make_ascii_lowercase();
+                if let Some(c) = word.first_mut() {
+                    c.make_ascii_uppercase();
+                }
+            }
+            Op::InvertCapitalize => {
+                word.make_ascii_uppercase();
+                if let Some(c) = word.first_mut() {
+                    c.make_ascii_lowercase();
+                }
+            }
+            Op::ToggleAll => word.iter_mut().for_each(toggle),
+            Op::ToggleAt(pos) => {
+                if let Some(c) = word.get_mut(at(pos)?) {
+                    toggle(c);
+                }
+            }
+            Op::ToggleAtSep(n, sep) => {
+                let n = at(n)?;
+                let mut occurrence = 0;
+                let mut toggle_next = false;
+                for c in word.iter_mut() {
+                    if *c == sep {
+                        if occurrence == n {
+                            toggle_next = true;
+                        } else {
+                            occurrence += 1;
+                        }
+                    } else if toggle_next {
+                        toggle(c);
+                        break;
+                    }
+                }
+            }
+            Op::Reverse => word.reverse(),
+            Op::Duplicate => {
+                if fits(len) {
+                    word.extend_from_within(..);
+                }
+            }
+            Op::DuplicateTimes(n) => {
+                let n = at(n)?;
+                if fits(len * n) {
+                    for _ in 0..n {
+                        word.extend_from_within(..len);
+                    }
+                }
+            }
+            Op::Reflect => {
+                if fits(len) {
+                    word.extend_from_within(..);
+                    word[len..].reverse();
+                }
+            }
+            Op::RotateLeft => {
+                if len > 0 {
+                    word.rotate_left(1);
+                }
+            }
+            Op::RotateRight => {
+                if len > 0 {
+                    word.rotate_right(1);
+                }
+            }
+            Op::Append(c) => {
+                if fits(1) {
+                    word.push(c);
+                }
+            }
+            Op::Prepend(c) => {
+                if fits(1) {
+                    word.insert(0, c);
+                }
+            }
+            Op::DeleteFirst => {
+                if len > 0 {
+                    word.remove(0);
+                }
+            }
+            Op::DeleteLast => {
+                word.pop();
+            }
+            Op::DeleteAt(pos) => {
+                let pos = at(pos)?;
+                if pos < len {
+                    word.remove(pos);
+                }
+            }
+            Op::Extract(pos, n) => {
+                let (pos, n) = (at(pos)?, at(n)?);
+                if pos < len && pos + n <= len {
+                    word.truncate(pos + n);
+                    word.drain(..pos);
+                }
+            }
+            Op::Omit(pos, n) => {
+                let (pos, n) = (at(pos)?, at(n)?);
+                if pos < len && pos + n <= len {
+                    word.drain(pos..pos + n);
+                }
+            }
+            Op::Insert(pos, c) => {
+                let pos = at(pos)?;
+                if pos <= len && fits(1) {
+                    word.insert(pos, c);
+                }
+            }
+            Op::Overstrike(pos, c) => {
+                if let Some(x) = word.get_mut(at(pos)?) {
+                    *x = c;
+                }
+            }
+            Op::TruncateAt(pos) => {
+                let pos = at(pos)?;
+                word.truncate(pos);
+            }
+            Op::Replace(class, to) => {
+                for c in word.iter_mut().filter(|c| class.matches(**c)) {
+                    *c = to;
+                }
+            }
+            Op::Purge(class) => word.retain(|&c| !class.matches(c)),
+            Op::DupeFirstChar(n) | Op::DupeLastChar(n) => {
+                let n = at(n)?;
+                if len > 0 && fits(n) {
+                    let pos = if matches!(op, Op::DupeFirstChar(_)) {
+                        0
+                    } else {
+                        len - 1
+                    };
+                    let c = word[pos];
+                    word.splice(pos..pos, std::iter::repeat_n(c, n));
+                }
+            }
+            Op::DupeAllChars => {
+                if len > 0 && fits(len) {
+                    *word = word.iter().flat_map(|&c| [c, c]).collect();
+                }
+            }
+            Op::DupeBlockFirst(n) => {
+                let n = at(n)?;
+                if n <= len && fits(n) {
+                    word.splice(0..0, word[..n].to_vec());
+                }
+            }
+            Op::DupeBlockLast(n) => {
+                let n = at(n)?;
+                if n <= len && fits(n) {
+                    word.extend_from_within(len - n..);
+                }
+            }
+            Op::SwapFirst => {
+                if len >= 2 {
+                    word.swap(0, 1);
+                }
+            }
+            Op::SwapLast => {
+                if len >= 2 {
+                    word.swap(len - 1, len - 2);
+                }
+            }
+            Op::SwapAt(a, b) => {
+                let (a, b) = (at(a)?, at(b)?);
+                if a < len && b < len {
+                    word.swap(a, b);
+                }
+            }
+            Op::ShiftLeft(pos) => {
+                if let Some(c) = word.get_mut(at(pos)?) {
+                    *c <<= 1;
+                }
+            }
+            Op::ShiftRight(pos) => {
+                if let Some(c) = word.get_mut(at(pos)?) {
+                    *c >>= 1;
+                }
+            }
+            Op::Increment(pos) => {
+                if let Some(c) = word.get_mut(at(pos)?) {
+                    *c = c.wrapping_add(1);
+                }
+            }
+            Op::Decrement(pos) => {
+                if let Some(c) = word.get_mut(at(pos)?) {
+                    *c = c.wrapping_sub(1);
+                }
+            }
+            Op::ReplaceNext(pos) => {
+                let pos = at(pos)?;
+                if pos + 1 < len {
+                    word[pos] = word[pos + 1];
+                }
+            }
+            Op::ReplacePrev(pos) => {
+                let pos = at(pos)?;
+                if pos >= 1 && pos < len {
+                    word[pos] = word[pos - 1];
+                }
+            }
+            Op::Title(sep) => {
+                let mut upper_next = true;
+                for c in word.iter_mut() {
+                    if sep.matches(*c) {
+                        upper_next = true;
+                    } else if mem::take(&mut upper_next) {
+                        c.make_ascii_uppercase();
+                    } else {
+                        c.make_ascii_lowercase();
+                    }
+                }
+                if let Some(c) = word.first_mut() {
+                    c.make_ascii_uppercase();
+                }
+            }
+            Op::ToHexLower | Op::ToHexUpper => {
+                if fits(len) {
+                    let digits = if *op == Op::ToHexLower {
+                        b"0123456789abcdef"
+                    } else {
+                        b"0123456789ABCDEF"
+                    };
+                    *word = word
+                        .iter()
+                        .flat_map(|&c| [digits[(c >> 4) as usize], digits[(c & 15) as usize]])
+                        .collect();
+                }
+            }
+            Op::ExtractMemory(pos, n, dst) => {
+                let (pos, n, dst) = (at(pos)?, at(n)?, at(dst)?);
+                let mem = &self.mem;
+                if n == 0 || !fits(n) || dst > len || pos + n > mem.len() {
+                    return None;
+                }
+                word.splice(dst..dst, mem[pos..pos + n].iter().copied());
+            }
+            Op::AppendMemory => {
+                if self.mem.is_empty() || !fits(self.mem.len()) {
+                    return None;
+                }
+                word.extend_from_slice(&self.mem);
+            }
+            Op::PrependMemory => {
+                if self.mem.is_empty() || !fits(self.mem.len()) {
+                    return None;
+                }
+                word.splice(0..0, self.mem.iter().copied());
+            }
+            Op::Memorize => self.mem.clone_from(word),
+            Op::RejectLonger(n) => {
+                if len > at(n)? {
+                    return None;
+                }
+            }
+            Op::RejectShorter(n) => {
+                if len < at(n)? {
+                    return None;
+                }
+            }
+            Op::RejectNotLength(n) => {
+                if len != at(n)? {
+                    return None;
+                }
+            }
+            Op::RejectContain(class) => {
+                if self.find(class) {
+                    return None;
+                }
+            }
+            Op::RejectNotContain(class) => {
+                if !self.find(class) {
+                    return None;
+                }
+            }
+            Op::RejectNotFirst(class) => {
+                if !word.first().is_some_and(|&c| class.matches(c)) {
+                    return None;
+                }
+            }
+            Op::RejectNotLast(class) => {
+                if !word.last().is_some_and(|&c| class.matches(c)) {
+                    return None;
+                }
+            }
+            Op::RejectNotAt(pos, class) => {
+                if !word.get(at(pos)?).is_some_and(|&c| class.matches(c)) {
+                    return None;
+                }
+            }
+            Op::RejectFewer(n, class) => {
+                let n = at(n)?;
+                if n + 1 > len || !self.count_at_least(class, n) {
+                    return None;
+                }
+            }
+            Op::RejectUnchanged => {
+                if *word == self.mem {
+                    return None;
+                }
+            }
+        }
+        Some(())
+    }
+}
+
+fn toggle(c: &mut u8) {
+    if c.is_ascii_alphabetic() {
+        *c ^= 0x20;
+    }
+}
+
+impl Rule {
+    /// Parses a line of a rule file. Functions are separated by optional
+    /// spaces and any character may be given as `\xHH`.
+    pub fn parse(line: &[u8]) -> Result<Self, String> {
+        let buf = decode_hex_notation(line);
+        let mut parser = Parser { buf: &buf, pos: 0 };
+        let mut ops = Vec::new();
+        while parser.pos < buf.len() {
+            let start = parser.pos;
+            let op = parser.op().ok_or_else(|| {
+                format!(
+                    "invalid function '{}'",
+                    String::from_utf8_lossy(&buf[start..parser.pos.max(start + 1)])
+                )
+            })?;
+            if op != Op::Noop {
+                ops.push(op);
+            }
+        }
+        Ok(Self { ops })
+    }
+
+    /// Applies the rules one after the other to `word`, in place, like the
+    /// single rule hashcat chains together when `-r` is given more than once.
+    /// Returns false if one of the reject functions, or a memory function
+    /// that can't be applied, rejects the word. The memory starts out holding
+    /// the original word.
+    pub fn apply_chain<'a>(rules: impl IntoIterator<Item = &'a Rule>, word: &mut Vec<u8>) -> bool {
+        let mem = word.clone();
+        let mut run = Run {
+            word,
+            mem,
+            found: None,
+        };
+        rules
+            .into_iter()
+            .flat_map(|rule| &rule.ops)
+            .all(|op| run.apply(op).is_some())
+    }
+}
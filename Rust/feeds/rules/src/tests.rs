@@ -0,0 +1,223 @@
+/**
+ * Author......: See docs/credits.txt
+ * License.....: MIT
+ */
+use std::fs;
+
+use hashcat_feed::{
+    testing::{candidates, temp_file},
+    Feed, PW_MAX, REJECTED,
+};
+
+use crate::{load_rules, rule::Rule, Rules};
+
+const REPO: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../../..");
+
+fn apply(rule: &str, word: &str) -> Option<String> {
+    let rule = Rule::parse(rule.as_bytes()).unwrap();
+    let mut word = word.as_bytes().to_vec();
+    Rule::apply_chain([&rule], &mut word).then(|| String::from_utf8_lossy(&word).into_owned())
+}
+
+fn check(rule: &str, word: &str, expected: &str) {
+    assert_eq!(
+        apply(rule, word).as_deref(),
+        Some(expected),
+        "rule {}",
+        rule
+    );
+}
+
+#[test]
+fn test_mangle() {
+    let w = "p@ssW0rd";
+    check(":", w, "p@ssW0rd");
+    check("l", w, "p@ssw0rd");
+    check("u", w, "P@SSW0RD");
+    check("c", w, "P@ssw0rd");
+    check("C", w, "p@SSW0RD");
+    check("t", w, "P@SSw0RD");
+    check("T3", w, "p@sSW0rd");
+    check("r", w, "dr0Wss@p");
+    check("d", w, "p@ssW0rdp@ssW0rd");
+    check("p2", w, "p@ssW0rdp@ssW0rdp@ssW0rd");
+    check("f", w, "p@ssW0rddr0Wss@p");
+    check("{", w, "@ssW0rdp");
+    check("}", w, "dp@ssW0r");
+    check("$1 $2", w, "p@ssW0rd12");
+    check("^2^1", w, "12p@ssW0rd");
+    check("[", w, "@ssW0rd");
+    check("]", w, "p@ssW0r");
+    check("D3", w, "p@sW0rd");
+    check("x04", w, "p@ss");
+    check("O12", w, "psW0rd");
+    check("i4!", w, "p@ss!W0rd");
+    check("o3$", w, "p@s$W0rd");
+    check("'6", w, "p@ssW0");
+    check("ss$", w, "p@$$W0rd");
+    check("@s", w, "p@W0rd");
+    check("z2", w, "ppp@ssW0rd");
+    check("Z2", w, "p@ssW0rddd");
+    check("q", w, "pp@@ssssWW00rrdd");
+    check("k", w, "@pssW0rd");
+    check("K", w, "p@ssW0dr");
+    check("*34", w, "p@sWs0rd");
+    check("R2", w, "p@9sW0rd");
+    check("+2", w, "p@tsW0rd");
+    check("-1", w, "p?ssW0rd");
+    check(".1", w, "psssW0rd");
+    check(",1", w, "ppssW0rd");
+    check("y2", w, "p@p@ssW0rd");
+    check("Y2", w, "p@ssW0rdrd");
+    check("E", "p@ssW0rd w0rld", "P@ssw0rd W0rld");
+    check("e-", "pass-word", "Pass-Word");
+    check("30-", "pass-word-list", "pass-Word-list");
+    check("31-", "pass-word-list", "pass-word-List");
+    check("h", "ab", "6162");
+    check("H", "\n", "0A");
+
+    let mut word = b"p@ssW0rd".to_vec();
+    assert!(Rule::apply_chain([&Rule::parse(b"L2").unwrap()], &mut word));
+    assert_eq!(word, b"p@\xe6sW0rd");
+
+    // Functions growing the word to the buffer size leave it unchanged.
+    let long = "a".repeat(128);
+    check("d", &long, &long);
+    check("$b", &"a".repeat(255), &"a".repeat(255));
+    check("$b", &"a".repeat(254), &("a".repeat(254) + "b"));
+}
+
+#[test]
+fn test_memory() {
+    let w = "p@ssW0rd";
+    check("lMX428", w, "p@ssw0rdw0");
+    check("uMl4", w, "p@ssw0rdP@SSW0RD");
+    check("rMr6", w, "dr0Wss@pp@ssW0rd");
+    check("lMuX084", w, "P@SSp@ssw0rdW0RD");
+    // Without `M` the memory holds the original word.
+    check("l4", w, "p@ssw0rdp@ssW0rd");
+    assert_eq!(apply("X918", w), None);
+    assert_eq!(apply("rrQ", w), None);
+    check("rQ", w, "dr0Wss@p");
+}
+
+#[test]
+fn test_reject() {
+    let w = "p@ssW0rd";
+    assert_eq!(apply("<7", w), None);
+    check("<8", w, w);
+    assert_eq!(apply(">9", w), None);
+    check(">8", w, w);
+    assert_eq!(apply("_7", w), None);
+    check("_8", w, w);
+    assert_eq!(apply("!W", w), None);
+    check("!z", w, w);
+    assert_eq!(apply("/z", w), None);
+    check("/W", w, w);
+    assert_eq!(apply("(@", w), None);
+    check("(p", w, w);
+    assert_eq!(apply(")r", w), None);
+    check(")d", w, w);
+    assert_eq!(apply("=1a", w), None);
+    assert_eq!(apply("=Ad", w), None);
+    check("=1@", w, w);
+    check("%2s", w, w);
+    assert_eq!(apply("%3s", w), None);
+    assert_eq!(apply("(a", ""), None);
+
+    // `p` is the position found by the last `/` or `%`.
+    check("/sDp", w, "p@sW0rd");
+    check("%2sDp", w, "p@sW0rd");
+    assert_eq!(apply("Dp", w), None);
+}
+
+#[test]
+fn test_class_and_hex() {
+    let w = "p@ssW0rd";
+    check("~s?d_", w, "p@ssW_rd");
+    check("~s??!", "a?b", "a!b");
+    check("~@?l", w, "@W0");
+    check("~@?s", w, "pssW0rd");
+    check("~e?d", "ab1cd", "Ab1Cd");
+    assert_eq!(apply("~!?u", w), None);
+    check("~/?u", w, w);
+    check("~(?l", w, w);
+    assert_eq!(apply("~)?d", w), None);
+    check("~=5?d", w, w);
+    assert_eq!(apply("~%2?d", w), None);
+    check("~%1?dDp", w, "p@ssWrd");
+    check("$\\x21", w, "p@ssW0rd!");
+    check("s\\x73\\x53", w, "p@SSW0rd");
+
+    for rule in [
+        "T", "Tx", "a", "~x", "~s?x1", "~sd1", "X000", "$", "=1", "~=1?", "$\\x2",
+    ] {
+        assert!(Rule::parse(rule.as_bytes()).is_err(), "rule {}", rule);
+    }
+}
+
+#[test]
+fn test_rule_files() {
+    let best66 = load_rules(&format!("{}/rules/best66.rule", REPO)).unwrap();
+    assert_eq!(best66.len(), 66);
+    let dive = load_rules(&format!("{}/rules/dive.rule", REPO)).unwrap();
+    assert_eq!(dive.len(), 98670);
+}
+
+#[test]
+fn test_feed() {
+    let wordlist = temp_file("feed.dict", "pass\nword\r\n$HEX[41]\n");
+    let rules = temp_file("feed.rule", "# comment\n:\n\nu\n<3\nT\n");
+    let chained = temp_file("feed_2.rule", "$1\n$2\n");
+    let path = |p: &std::path::Path| p.to_str().unwrap().to_string();
+
+    let feed = Rules::new(&[path(&wordlist), "-r".into(), path(&rules)]).unwrap();
+    assert_eq!(feed.keyspace(), Some(9));
+    let all = candidates(&feed, 0, 100);
+    assert_eq!(all, ["pass", "PASS", "word", "WORD", "A", "A", "A"]);
+
+    // A rejected candidate takes its offset, so batches stay in step.
+    let mut cursor = feed.thread_init().unwrap();
+    let mut out = [0u8; PW_MAX];
+    let lens: Vec<_> = std::iter::from_fn(|| feed.next(&mut cursor, &mut out).unwrap()).collect();
+    assert_eq!(lens, [4, 4, REJECTED, 4, 4, REJECTED, 1, 1, 1]);
+    assert!(candidates(&feed, 2, 1).is_empty());
+    assert_eq!(candidates(&feed, 2, 2), ["word"]);
+    assert!(candidates(&feed, 5, 1).is_empty());
+    assert_eq!(candidates(&feed, 8, 1), ["A"]);
+    assert!(candidates(&feed, 9, 1).is_empty());
+
+    let feed = Rules::new(&[
+        format!("--rules-file={}", path(&rules)),
+        path(&wordlist),
+        "-r".into(),
+        path(&chained),
+    ])
+    .unwrap();
+    assert_eq!(feed.keyspace(), Some(18));
+    let all = candidates(&feed, 0, 5);
+    assert_eq!(all, ["pass1", "PASS1", "pass2", "PASS2"]);
+
+    assert!(Rules::new(&[path(&wordlist)]).is_err());
+    assert!(Rules::new(&["-r".into(), path(&rules)]).is_err());
+    assert!(Rules::new(&[path(&wordlist), "-r".into(), path(&wordlist), "x".into()]).is_err());
+
+    fs::remove_file(wordlist).unwrap();
+    fs::remove_file(rules).unwrap();
+    fs::remove_file(chained).unwrap();
+}
+
+#[test]
+fn test_dive_on_example_dict() {
+    let feed = Rules::new(&[
+        format!("{}/example.dict", REPO),
+        "-r".into(),
+        format!("{}/rules/best66.rule", REPO),
+    ])
+    .unwrap();
+    assert_eq!(feed.keyspace(), Some(128416 * 66));
+    let offset = 66 * 1000 + 5;
+    let mut seq = candidates(&feed, offset - 5, 5);
+    seq.extend(candidates(&feed, offset, 5));
+    assert_eq!(candidates(&feed, offset - 5, 10), seq);
+}
@@ -0,0 +1,414 @@
+/**
+ * Author......: See docs/credits.txt
+ * License.....: MIT
+ */
+use std::{
+    cell::RefCell,
+    ffi::{c_char, c_int, c_void, CStr},
+    mem,
+    path::Path,
+    ptr, slice,
+    sync::{Once, OnceLock},
+};
+
+use hashcat_sys::{bridge_context_t, event, generic_io_t, generic_io_tmp_t, salt_t};
+
+use crate::{Bridge, GlobalContext, HexCases, Options, Output, Salt, ThreadContext};
+
+static LOG_ERROR_ONCE: Once = Once::new();
+
+/// Hashcat calls the kernel loop from several threads at once, so only the
+/// first error is printed.
+fn log_error_once(err: &str) {
+    LOG_ERROR_ONCE.call_once(|| event::error(err));
+}
+
+/// Count, element size and address of a buffer passed to `new_context`.
+pub type RawBuf = (c_int, c_int, *const c_char);
+
+unsafe fn vec_from_raw_buf<T: Clone>((cnt, size, buf): RawBuf) -> Vec<T> {
+    if buf.is_null() {
+        return vec![];
+    }
+    assert_eq!(size as usize, mem::size_of::<T>());
+    Vec::from(unsafe { slice::from_raw_parts(buf as *const T, cnt as usize) })
+}
+
+unsafe fn string_from_ptr(ptr: *const c_char) -> String {
+    if ptr.is_null() {
+        String::new()
+    } else {
+        unsafe { CStr::from_ptr(ptr).to_str().unwrap_or_default().to_string() }
+    }
+}
+
+/// What `new_context` hands to hashcat: the session data and the state
+/// created by `Bridge::thread_init`.
+struct Unit<B: Bridge> {
+    ctx: ThreadContext,
+    state: RefCell<Option<B::ThreadState>>,
+    /// The hex cases of each salt and self-test salt, worked out once since
+    /// unsalted hash lists put all digests under one salt.
+    hex_cases: Vec<HexCases>,
+    st_hex_cases: Vec<HexCases>,
+}
+
+/// Encodes the digests of one candidate for the stored hashes of its salt and
+/// copies them into its output slots.
+fn write_outputs(
+    io: &mut generic_io_tmp_t,
+    outputs: Vec<Output>,
+    cases: HexCases,
+) -> Result<(), String> {
+    io.clear_outputs();
+    for output in outputs {
+        for encoded in output.encode_all(cases) {
+            io.push_output(&encoded)
+                .map_err(|e| format!("calc_hash returned invalid digests: {}", e))?;
+        }
+    }
+    Ok(())
+}
+
+/// The bridge instance of a library, created by `global_init`.
+pub struct Instance<B: Bridge> {
+    bridge: OnceLock<B>,
+    info: OnceLock<String>,
+    options: OnceLock<Options>,
+}
+
+impl<B: Bridge> Default for Instance<B> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<B: Bridge> Instance<B> {
+    pub const fn new() -> Self {
+        Self {
+            bridge: OnceLock::new(),
+            info: OnceLock::new(),
+            options: OnceLock::new(),
+        }
+    }
+
+    fn bridge(&self) -> &B {
+        self.bridge.get().expect("global_init not called")
+    }
+
+    /// # Safety
+    ///
+    /// `buf` must point to at least `buf_size` writable bytes.
+    pub unsafe fn get_info(&self, buf: *mut c_char, buf_size: c_int) -> c_int {
+        assert!(buf_size > 0);
+        let info = self.info.get().map(String::as_str).unwrap_or_default();
+        let n = info.len().min(buf_size as usize);
+        unsafe {
+            ptr::copy_nonoverlapping(info.as_ptr(), buf as *mut u8, n);
+        }
+        n as c_int
+    }
+
+    /// # Safety
+    ///
+    /// `ctx` must point to a valid `bridge_context_t`.
+    pub unsafe fn global_init(&self, ctx: *const bridge_context_t) -> bool {
+        assert!(!ctx.is_null());
+        let ctx = unsafe { &*ctx };
+        assert!(!ctx.dynlib_filename.is_null());
+
+        let dynlib_name = unsafe { string_from_ptr(ctx.dynlib_filename) };
+        let dynlib_name = Path::new(&dynlib_name)
+            .file_name()
+            .and_then(|x| x.to_str())
+            .unwrap_or_default()
+            .to_string();
+        let bridge_parameter3 = unsafe { string_from_ptr(ctx.bridge_parameter3) };
+        let bridge_parameter4 = unsafe { string_from_ptr(ctx.bridge_parameter4) };
+        let options = if B::OPTIONS.is_empty() && !B::ACCEPTS_EXTRA_OPTIONS {
+            Options::default()
+        } else {
+            let sources = [
+                ("--bridge-parameter3", bridge_parameter3.as_str()),
+                ("--bridge-parameter4", bridge_parameter4.as_str()),
+            ];
+            match Options::parse(&sources, B::OPTIONS, B::ACCEPTS_EXTRA_OPTIONS) {
+                Ok(options) => options,
+                Err(err) => {
+                    event::error(&err);
+                    return false;
+                }
+            }
+        };
+        let global_ctx = unsafe {
+            GlobalContext {
+                dynlib_name,
+                bridge_parameter1: string_from_ptr(ctx.bridge_parameter1),
+                bridge_parameter2: string_from_ptr(ctx.bridge_parameter2),
+                bridge_parameter3,
+                bridge_parameter4,
+                options,
+            }
+        };
+
+        let bridge = match B::global_init(&global_ctx) {
+            Ok(bridge) => bridge,
+            Err(err) => {
+                event::error(&err);
+                return false;
+            }
+        };
+        let info = match bridge.info() {
+            Some(info) => format!("Rust [{}] [{}]", global_ctx.dynlib_name, info),
+            None => format!("Rust [{}]", global_ctx.dynlib_name),
+        };
+        if self.bridge.set(bridge).is_err()
+            || self.info.set(info).is_err()
+            || self.options.set(global_ctx.options).is_err()
+        {
+            panic!("global_init called twice");
+        }
+        true
+    }
+
+    pub fn global_term(&self) {
+        if let Some(bridge) = self.bridge.get() {
+            bridge.global_term();
+        }
+    }
+
+    /// # Safety
+    ///
+    /// All pointers must be null or valid for the given counts and sizes.
+    pub unsafe fn new_context(
+        &self,
+        module_name: *const c_char,
+        salts: RawBuf,
+        esalts: RawBuf,
+        st_salts: RawBuf,
+        st_esalts: RawBuf,
+        bridge_parameters: [*const c_char; 4],
+    ) -> *mut c_void {
+        assert!(!module_name.is_null());
+        let [bridge_parameter1, bridge_parameter2, bridge_parameter3, bridge_parameter4] =
+            bridge_parameters.map(|p| unsafe { string_from_ptr(p) });
+        let ctx = unsafe {
+            ThreadContext {
+                module_name: string_from_ptr(module_name),
+                salts: vec_from_raw_buf::<salt_t>(salts),
+                esalts: vec_from_raw_buf::<generic_io_t>(esalts),
+                st_salts: vec_from_raw_buf::<salt_t>(st_salts),
+                st_esalts: vec_from_raw_buf::<generic_io_t>(st_esalts),
+                bridge_parameter1,
+                bridge_parameter2,
+                bridge_parameter3,
+                bridge_parameter4,
+                options: self.options.get().cloned().unwrap_or_default(),
+            }
+        };
+        let hex_cases = |salts: &[salt_t], is_selftest| {
+            (0..salts.len())
+                .map(|id| HexCases::of(ctx.salt(id, is_selftest).digests()))
+                .collect()
+        };
+        let (hex_cases, st_hex_cases) =
+            (hex_cases(&ctx.salts, false), hex_cases(&ctx.st_salts, true));
+        Box::into_raw(Box::new(Unit::<B> {
+            ctx,
+            state: RefCell::new(None),
+            hex_cases,
+            st_hex_cases,
+        })) as *mut c_void
+    }
+
+    /// # Safety
+    ///
+    /// `ctx` must have been returned by `new_context` and not dropped yet.
+    pub unsafe fn drop_context(&self, ctx: *mut c_void) {
+        assert!(!ctx.is_null());
+        drop(unsafe { Box::from_raw(ctx as *mut Unit<B>) });
+    }
+
+    /// # Safety
+    ///
+    /// `ctx` must have been returned by `new_context` and not dropped yet.
+    pub unsafe fn thread_init(&self, ctx: *mut c_void) {
+        assert!(!ctx.is_null());
+        let unit = unsafe { &*ctx.cast::<Unit<B>>() };
+        match self.bridge().thread_init(&unit.ctx) {
+            Ok(state) => *unit.state.borrow_mut() = Some(state),
+            // Hashcat ignores the result of thread_init, the error is
+            // reported again by the first kernel_loop call.
+            Err(err) => log_error_once(&err),
+        }
+    }
+
+    /// # Safety
+    ///
+    /// `ctx` must have been returned by `new_context` and not dropped yet.
+    pub unsafe fn thread_term(&self, ctx: *mut c_void) {
+        assert!(!ctx.is_null());
+        let unit = unsafe { &*ctx.cast::<Unit<B>>() };
+        if let Some(state) = unit.state.borrow_mut().as_mut() {
+            self.bridge().thread_term(&unit.ctx, state);
+        }
+    }
+
+    /// # Safety
+    ///
+    /// `ctx` must have been returned by `new_context` and not dropped yet,
+    /// `io` must point to `pws_cnt` elements.
+    pub unsafe fn kernel_loop(
+        &self,
+        ctx: *mut c_void,
+        io: *mut generic_io_tmp_t,
+        pws_cnt: u64,
+        salt_id: c_int,
+        is_selftest: bool,
+    ) -> bool {
+        assert!(!ctx.is_null());
+        assert!(!io.is_null());
+        let unit = unsafe { &*ctx.cast::<Unit<B>>() };
+        let io = unsafe { slice::from_raw_parts_mut(io, pws_cnt as usize) };
+
+        let salt = unit.ctx.salt(salt_id as usize, is_selftest);
+        let cases = match is_selftest {
+            false => unit.hex_cases[salt.id],
+            true => unit.st_hex_cases[salt.id],
+        };
+        let result = match unit.state.borrow_mut().as_mut() {
+            Some(state) => self.process_batch(state, io, &salt, cases),
+            None => Err("thread_init failed".to_string()),
+        };
+        match result {
+            Ok(()) => true,
+            Err(err) => {
+                log_error_once(&err);
+                false
+            }
+        }
+    }
+
+    fn process_batch(
+        &self,
+        state: &mut B::ThreadState,
+        io: &mut [generic_io_tmp_t],
+        salt: &Salt,
+        cases: HexCases,
+    ) -> Result<(), String> {
+        let passwords: Vec<&[u8]> = io.iter().map(|x| x.password()).collect();
+        let results = self.bridge().process_batch(state, &passwords, salt)?;
+        if results.len() != io.len() {
+            return Err(format!(
+                "process_batch returned {} results for {} passwords",
+                results.len(),
+                io.len()
+            ));
+        }
+        for (dst, digests) in io.iter_mut().zip(results) {
+            write_outputs(dst, digests, cases)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Output;
+
+    struct Upper;
+
+    impl Bridge for Upper {
+        type ThreadState = usize;
+
+        fn global_init(_ctx: &GlobalContext) -> Result<Self, String> {
+            Ok(Upper)
+        }
+
+        fn thread_init(&self, _ctx: &ThreadContext) -> Result<usize, String> {
+            Ok(0)
+        }
+
+        fn calc_hash(
+            &self,
+            calls: &mut usize,
+            password: &[u8],
+            salt: &Salt,
+        ) -> Result<Vec<Output>, String> {
+            *calls += 1;
+            if password.is_empty() {
+                return Ok(vec![Output::Text(vec![0; 257])]);
+            }
+            Ok(vec![
+                Output::Text(password.to_ascii_uppercase()),
+                Output::Binary(salt.salt().to_vec()),
+            ])
+        }
+    }
+
+    fn io_with_password(password: &[u8]) -> generic_io_tmp_t {
+        let mut io: generic_io_tmp_t = unsafe { mem::zeroed() };
+        unsafe {
+            ptr::copy_nonoverlapping(
+                password.as_ptr(),
+                io.pw_buf.as_mut_ptr() as *mut u8,
+                password.len(),
+            );
+        }
+        io.pw_len = password.len() as u32;
+        io
+    }
+
+    fn outputs(io: &generic_io_tmp_t) -> Vec<&[u8]> {
+        io.outputs().collect()
+    }
+
+    #[test]
+    fn test_kernel_loop() {
+        let instance = Instance::<Upper>::new();
+        instance.bridge.set(Upper).ok().unwrap();
+
+        let mut salt: salt_t = unsafe { mem::zeroed() };
+        salt.digests_cnt = 1;
+        let mut esalt: generic_io_t = unsafe { mem::zeroed() };
+        unsafe {
+            ptr::copy_nonoverlapping(
+                b"pepper".as_ptr(),
+                esalt.salt_buf.as_mut_ptr() as *mut u8,
+                6,
+            );
+        }
+        esalt.salt_len = 6;
+        let raw = |p: *const c_char, size: usize| (1, size as c_int, p);
+
+        unsafe {
+            let ctx = instance.new_context(
+                c"module".as_ptr(),
+                raw(&salt as *const _ as *const c_char, mem::size_of::<salt_t>()),
+                raw(
+                    &esalt as *const _ as *const c_char,
+                    mem::size_of::<generic_io_t>(),
+                ),
+                (0, mem::size_of::<salt_t>() as c_int, ptr::null()),
+                (0, mem::size_of::<generic_io_t>() as c_int, ptr::null()),
+                [ptr::null(); 4],
+            );
+            instance.thread_init(ctx);
+
+            let mut io = [io_with_password(b"hashcat"), io_with_password(b"abc")];
+            assert!(instance.kernel_loop(ctx, io.as_mut_ptr(), 2, 0, false));
+            assert_eq!(outputs(&io[0]), [&b"HASHCAT"[..], b"706570706572"]);
+            assert_eq!(outputs(&io[1]), [&b"ABC"[..], b"706570706572"]);
+
+            let unit = &*ctx.cast::<Unit<Upper>>();
+            assert_eq!(*unit.state.borrow(), Some(2));
+
+            let mut io = [io_with_password(b"")];
+            assert!(!instance.kernel_loop(ctx, io.as_mut_ptr(), 1, 0, false));
+
+            instance.thread_term(ctx);
+            instance.drop_context(ctx);
+        }
+    }
+}
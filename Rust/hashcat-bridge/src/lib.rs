@@ -0,0 +1,478 @@
+/**
+ * Author......: See docs/credits.txt
+ * License.....: MIT
+ */
+use hashcat_sys::{generic_io_t, salt_t};
+
+#[doc(hidden)]
+pub mod export;
+mod options;
+
+pub use hashcat_sys::{self, event};
+pub use options::{OptionSpec, Options};
+
+/// Parameters available when the bridge is loaded.
+pub struct GlobalContext {
+    /// File name of the bridge library, without its directory.
+    pub dynlib_name: String,
+
+    pub bridge_parameter1: String,
+    pub bridge_parameter2: String,
+    pub bridge_parameter3: String,
+    pub bridge_parameter4: String,
+
+    /// The options in parameters 3 and 4, see `Bridge::OPTIONS`.
+    pub options: Options,
+}
+
+/// The hashes and parameters of a session. Every compute thread gets its own
+/// copy.
+pub struct ThreadContext {
+    pub module_name: String,
+
+    pub salts: Vec<salt_t>,
+    pub esalts: Vec<generic_io_t>,
+    pub st_salts: Vec<salt_t>,
+    pub st_esalts: Vec<generic_io_t>,
+
+    pub bridge_parameter1: String,
+    pub bridge_parameter2: String,
+    pub bridge_parameter3: String,
+    pub bridge_parameter4: String,
+
+    pub options: Options,
+}
+
+impl ThreadContext {
+    /// Returns the salt a batch is hashed against. The self-test uses its own
+    /// set of salts.
+    pub fn salt(&self, salt_id: usize, is_selftest: bool) -> Salt<'_> {
+        let (salts, esalts) = if is_selftest {
+            (&self.st_salts, &self.st_esalts)
+        } else {
+            (&self.salts, &self.esalts)
+        };
+        // Hashcat keeps one esalt per digest, the digests of a salt follow
+        // each other.
+        let raw = &salts[salt_id];
+        let start = raw.digests_offset as usize;
+        Salt {
+            id: salt_id,
+            is_selftest,
+            raw,
+            esalts: &esalts[start..start + raw.digests_cnt as usize],
+        }
+    }
+}
+
+/// A salt together with the esalts of all hash lines using it, which hold
+/// the fields the module parsed from each line.
+#[derive(Clone, Copy)]
+pub struct Salt<'a> {
+    pub id: usize,
+    pub is_selftest: bool,
+    pub raw: &'a salt_t,
+    pub esalts: &'a [generic_io_t],
+}
+
+impl<'a> Salt<'a> {
+    /// The salt part of the hash lines, everything after the first `*`.
+    /// Lines with the same salt part share a salt.
+    pub fn salt(&self) -> &'a [u8] {
+        self.esalts.first().map_or(&[], |esalt| esalt.salt())
+    }
+
+    /// The hash parts of the hash lines using this salt, everything before
+    /// the first `*`, in the order of the digests.
+    pub fn digests(&self) -> impl Iterator<Item = &'a [u8]> + use<'a> {
+        self.esalts.iter().map(|esalt| esalt.stored_hash())
+    }
+
+    // The cost parameters below are whatever the module stored in `salt_t`
+    // while parsing the hash line, zero if it doesn't set them. Note that
+    // many modules store the iteration count minus one.
+
+    pub fn salt_iter(&self) -> u32 {
+        self.raw.salt_iter
+    }
+
+    pub fn salt_iter2(&self) -> u32 {
+        self.raw.salt_iter2
+    }
+
+    pub fn salt_repeats(&self) -> u32 {
+        self.raw.salt_repeats
+    }
+
+    pub fn scrypt_n(&self) -> u32 {
+        self.raw.scrypt_N
+    }
+
+    pub fn scrypt_r(&self) -> u32 {
+        self.raw.scrypt_r
+    }
+
+    pub fn scrypt_p(&self) -> u32 {
+        self.raw.scrypt_p
+    }
+
+    /// The 8-byte signature some modules keep next to the salt.
+    pub fn salt_sign(&self) -> [u8; 8] {
+        let [lo, hi] = self.raw.salt_sign;
+        let mut sign = [0; 8];
+        sign[..4].copy_from_slice(&lo.to_ne_bytes());
+        sign[4..].copy_from_slice(&hi.to_ne_bytes());
+        sign
+    }
+}
+
+/// A candidate digest. Hashcat compares outputs byte for byte with the hash
+/// part of the hash line, so binary digests are hex encoded first.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Output {
+    /// Compared as is, e.g. a digest already encoded by the bridge.
+    Text(Vec<u8>),
+    /// A raw digest, hex encoded in the letter case of the stored hashes,
+    /// in both cases if the hash lines of the salt mix them.
+    Binary(Vec<u8>),
+}
+
+/// Whether a stored hex hash is written in upper case.
+fn is_uppercase_hex(stored_hash: &[u8]) -> bool {
+    stored_hash.iter().any(|b| matches!(b, b'A'..=b'F'))
+        && !stored_hash.iter().any(|b| matches!(b, b'a'..=b'f'))
+}
+
+/// The letter cases the hex encoded hashes of a salt are written in. The
+/// hash lines of a salt may mix them.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct HexCases {
+    pub lower: bool,
+    pub upper: bool,
+}
+
+impl HexCases {
+    pub fn of<'a>(stored_hashes: impl IntoIterator<Item = &'a [u8]>) -> Self {
+        let mut cases = Self::default();
+        for stored_hash in stored_hashes {
+            if is_uppercase_hex(stored_hash) {
+                cases.upper = true;
+            } else {
+                cases.lower = true;
+            }
+        }
+        cases
+    }
+}
+
+impl Output {
+    /// Returns the bytes hashcat compares with `stored_hash`.
+    pub fn encode(self, stored_hash: &[u8]) -> Vec<u8> {
+        match self {
+            Output::Text(text) => text,
+            Output::Binary(digest) => {
+                let hex = hex::encode(digest).into_bytes();
+                if is_uppercase_hex(stored_hash) {
+                    hex.to_ascii_uppercase()
+                } else {
+                    hex
+                }
+            }
+        }
+    }
+
+    /// Returns the bytes hashcat compares with the stored hashes of a salt,
+    /// once per letter case they use.
+    pub fn encode_all(self, cases: HexCases) -> Vec<Vec<u8>> {
+        let Output::Binary(digest) = self else {
+            return vec![self.encode(&[])];
+        };
+        let hex = hex::encode(digest).into_bytes();
+        match (cases.lower, cases.upper) {
+            (true, true) => vec![hex.to_ascii_uppercase(), hex],
+            (false, true) => vec![hex.to_ascii_uppercase()],
+            _ => vec![hex],
+        }
+    }
+}
+
+impl From<String> for Output {
+    fn from(text: String) -> Self {
+        Output::Text(text.into_bytes())
+    }
+}
+
+impl From<&str> for Output {
+    fn from(text: &str) -> Self {
+        Output::Text(text.as_bytes().to_vec())
+    }
+}
+
+/// A Rust bridge. One instance is created by `global_init` and shared by all
+/// compute threads, each of which keeps its own `ThreadState`.
+///
+/// Errors are printed by the generated glue code; an error in `global_init`
+/// stops hashcat from loading the bridge, an error while hashing aborts the
+/// session.
+pub trait Bridge: Sized + Send + Sync + 'static {
+    /// State owned by a single compute thread, e.g. scratch buffers.
+    type ThreadState;
+
+    /// The `key=value` options the bridge accepts in `--bridge-parameter3`
+    /// and `--bridge-parameter4`. Unknown options make `global_init` fail.
+    /// Bridges that declare none get both parameters unparsed.
+    const OPTIONS: &'static [OptionSpec] = &[];
+
+    /// Accept keys not declared in `OPTIONS` too, for bridges that take
+    /// user-defined names. Such a bridge checks them itself in `global_init`.
+    const ACCEPTS_EXTRA_OPTIONS: bool = false;
+
+    fn global_init(ctx: &GlobalContext) -> Result<Self, String>;
+
+    /// Text shown next to the library name in the device list.
+    fn info(&self) -> Option<String> {
+        None
+    }
+
+    fn global_term(&self) {}
+
+    fn thread_init(&self, ctx: &ThreadContext) -> Result<Self::ThreadState, String>;
+
+    #[allow(unused_variables)]
+    fn thread_term(&self, ctx: &ThreadContext, state: &mut Self::ThreadState) {}
+
+    /// Hashes one candidate. Every returned digest is compared against the
+    /// stored hashes of the salt; at most 32 digests of up to 256 bytes each,
+    /// after encoding, are allowed.
+    fn calc_hash(
+        &self,
+        state: &mut Self::ThreadState,
+        password: &[u8],
+        salt: &Salt,
+    ) -> Result<Vec<Output>, String>;
+
+    /// Hashes a batch of candidates against the same salt. Override this to
+    /// share work between candidates; by default it calls `calc_hash` for
+    /// each of them.
+    fn process_batch(
+        &self,
+        state: &mut Self::ThreadState,
+        passwords: &[&[u8]],
+        salt: &Salt,
+    ) -> Result<Vec<Vec<Output>>, String> {
+        passwords
+            .iter()
+            .map(|password| self.calc_hash(state, password, salt))
+            .collect()
+    }
+}
+
+/// Generates the C functions hashcat expects from a bridge library for a
+/// type implementing [`Bridge`]. Use it once per crate.
+#[macro_export]
+macro_rules! export_bridge {
+    ($bridge:ty) => {
+        const _: () = {
+            use ::std::ffi::{c_char, c_int, c_void};
+
+            use $crate::{
+                export::Instance,
+                hashcat_sys::{bridge_context_t, generic_io_tmp_t},
+            };
+
+            static INSTANCE: Instance<$bridge> = Instance::new();
+
+            #[unsafe(no_mangle)]
+            pub extern "C" fn get_info(buf: *mut c_char, buf_size: c_int) -> c_int {
+                unsafe { INSTANCE.get_info(buf, buf_size) }
+            }
+
+            #[unsafe(no_mangle)]
+            pub extern "C" fn global_init(ctx: *const bridge_context_t) -> bool {
+                unsafe { INSTANCE.global_init(ctx) }
+            }
+
+            #[unsafe(no_mangle)]
+            pub extern "C" fn global_term(_ctx: *const bridge_context_t) {
+                INSTANCE.global_term()
+            }
+
+            #[unsafe(no_mangle)]
+            pub extern "C" fn new_context(
+                module_name: *const c_char,
+                salts_cnt: c_int,
+                salts_size: c_int,
+                salts_buf: *const c_char,
+                esalts_cnt: c_int,
+                esalts_size: c_int,
+                esalts_buf: *const c_char,
+                st_salts_cnt: c_int,
+                st_salts_size: c_int,
+                st_salts_buf: *const c_char,
+                st_esalts_cnt: c_int,
+                st_esalts_size: c_int,
+                st_esalts_buf: *const c_char,
+                bridge_parameter1: *const c_char,
+                bridge_parameter2: *const c_char,
+                bridge_parameter3: *const c_char,
+                bridge_parameter4: *const c_char,
+            ) -> *mut c_void {
+                unsafe {
+                    INSTANCE.new_context(
+                        module_name,
+                        (salts_cnt, salts_size, salts_buf),
+                        (esalts_cnt, esalts_size, esalts_buf),
+                        (st_salts_cnt, st_salts_size, st_salts_buf),
+                        (st_esalts_cnt, st_esalts_size, st_esalts_buf),
+                        [
+                            bridge_parameter1,
+                            bridge_parameter2,
+                            bridge_parameter3,
+                            bridge_parameter4,
+                        ],
+                    )
+                }
+            }
+
+            #[unsafe(no_mangle)]
+            pub extern "C" fn drop_context(ctx: *mut c_void) {
+                unsafe { INSTANCE.drop_context(ctx) }
+            }
+
+            #[unsafe(no_mangle)]
+            pub extern "C" fn thread_init(ctx: *mut c_void) {
+                unsafe { INSTANCE.thread_init(ctx) }
+            }
+
+            #[unsafe(no_mangle)]
+            pub extern "C" fn thread_term(ctx: *mut c_void) {
+                unsafe { INSTANCE.thread_term(ctx) }
+            }
+
+            #[unsafe(no_mangle)]
+            pub extern "C" fn kernel_loop(
+                ctx: *mut c_void,
+                io: *mut generic_io_tmp_t,
+                pws_cnt: u64,
+                salt_id: c_int,
+                is_selftest: bool,
+            ) -> bool {
+                unsafe { INSTANCE.kernel_loop(ctx, io, pws_cnt, salt_id, is_selftest) }
+            }
+        };
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn esalt(hash: &[u8], salt: &[u8]) -> generic_io_t {
+        let mut esalt: generic_io_t = unsafe { std::mem::zeroed() };
+        let copy = |dst: &mut [u32], src: &[u8]| {
+            for (word, chunk) in dst.iter_mut().zip(src.chunks(4)) {
+                let mut bytes = [0; 4];
+                bytes[..chunk.len()].copy_from_slice(chunk);
+                *word = u32::from_ne_bytes(bytes);
+            }
+        };
+        copy(&mut esalt.hash_buf, hash);
+        esalt.hash_len = hash.len() as u32;
+        copy(&mut esalt.salt_buf, salt);
+        esalt.salt_len = salt.len() as u32;
+        esalt
+    }
+
+    #[test]
+    fn test_output_encode() {
+        let digest = Output::Binary(vec![0xab, 0x01]);
+        assert_eq!(digest.clone().encode(b"ab01"), b"ab01");
+        assert_eq!(digest.clone().encode(b"AB01"), b"AB01");
+        assert_eq!(digest.encode(b"0000"), b"ab01");
+        assert_eq!(Output::from("AbC").encode(b"abc"), b"AbC");
+
+        let stored: [&[u8]; 3] = [b"AB01", b"0000", b"ab01"];
+        let mixed = HexCases::of(stored);
+        assert_eq!(
+            mixed,
+            HexCases {
+                lower: true,
+                upper: true
+            }
+        );
+        assert_eq!(
+            Output::Binary(vec![0xab, 0x01]).encode_all(mixed),
+            [b"AB01", b"ab01"]
+        );
+        let upper = HexCases::of([&b"FFFF"[..]]);
+        assert_eq!(
+            Output::Binary(vec![0xab, 0x01]).encode_all(upper),
+            [b"AB01"]
+        );
+        let none = HexCases::of([]);
+        assert_eq!(Output::Binary(vec![0xab]).encode_all(none), [b"ab"]);
+        assert_eq!(Output::from("AbC").encode_all(mixed), [b"AbC"]);
+    }
+
+    #[test]
+    fn test_salt_accessors() {
+        let mut raw: salt_t = unsafe { std::mem::zeroed() };
+        raw.salt_iter = 999;
+        raw.scrypt_N = 16384;
+        raw.scrypt_r = 8;
+        raw.scrypt_p = 1;
+        raw.salt_sign = [u32::from_ne_bytes(*b"$2a$"), u32::from_ne_bytes(*b"05\0\0")];
+        let esalt = esalt(b"abc", b"");
+        let salt = Salt {
+            id: 0,
+            is_selftest: false,
+            raw: &raw,
+            esalts: &[esalt],
+        };
+        assert_eq!(salt.salt_iter(), 999);
+        assert_eq!(salt.salt_iter2(), 0);
+        assert_eq!(
+            (salt.scrypt_n(), salt.scrypt_r(), salt.scrypt_p()),
+            (16384, 8, 1)
+        );
+        assert_eq!(&salt.salt_sign(), b"$2a$05\0\0");
+        assert_eq!(salt.digests().collect::<Vec<_>>(), [b"abc"]);
+        assert_eq!(salt.salt(), b"");
+    }
+
+    #[test]
+    fn test_salt_digests() {
+        // Two hash lines share the first salt, hashcat stores their esalts
+        // next to each other.
+        let mut salts: [salt_t; 2] = unsafe { std::mem::zeroed() };
+        salts[0].digests_cnt = 2;
+        salts[1].digests_cnt = 1;
+        salts[1].digests_offset = 2;
+        let ctx = ThreadContext {
+            module_name: String::new(),
+            salts: salts.to_vec(),
+            esalts: vec![
+                esalt(b"h1", b"s1"),
+                esalt(b"h2", b"s1"),
+                esalt(b"h3", b"s2"),
+            ],
+            st_salts: vec![salts[1]],
+            st_esalts: vec![esalt(b"x", b"x"), esalt(b"x", b"x"), esalt(b"st", b"st")],
+            bridge_parameter1: String::new(),
+            bridge_parameter2: String::new(),
+            bridge_parameter3: String::new(),
+            bridge_parameter4: String::new(),
+            options: Options::default(),
+        };
+
+        let salt = ctx.salt(0, false);
+        assert_eq!(salt.salt(), b"s1");
+        assert_eq!(salt.digests().collect::<Vec<_>>(), [b"h1", b"h2"]);
+        let salt = ctx.salt(1, false);
+        assert_eq!(salt.salt(), b"s2");
+        assert_eq!(salt.digests().collect::<Vec<_>>(), [b"h3"]);
+        let salt = ctx.salt(0, true);
+        assert_eq!(salt.salt(), b"st");
+        assert_eq!(salt.digests().collect::<Vec<_>>(), [b"st"]);
+    }
+}
@@ -0,0 +1,152 @@
+/**
+ * Author......: See docs/credits.txt
+ * License.....: MIT
+ */
+use std::{fmt::Display, str::FromStr};
+
+/// An option a bridge accepts in `--bridge-parameter3` or
+/// `--bridge-parameter4`.
+pub struct OptionSpec {
+    pub name: &'static str,
+    /// Short description shown when an unknown option is given.
+    pub help: &'static str,
+}
+
+/// Options given as `key=value;key=value` in `--bridge-parameter3` and
+/// `--bridge-parameter4`, e.g. `--bridge-parameter3='cost=12;encoding=b64'`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Options(Vec<(String, String)>);
+
+impl Options {
+    /// Parses the options of each `(parameter name, value)` source. Every key
+    /// has to be declared in `specs`, unless `accept_extra` is set, and may
+    /// only be given once.
+    pub fn parse(
+        sources: &[(&str, &str)],
+        specs: &[OptionSpec],
+        accept_extra: bool,
+    ) -> Result<Self, String> {
+        let mut options: Vec<(String, String)> = vec![];
+        for &(parameter, value) in sources {
+            for option in value.split(';').map(str::trim).filter(|o| !o.is_empty()) {
+                let Some((key, value)) = option
+                    .split_once('=')
+                    .filter(|(key, _)| !key.trim().is_empty())
+                else {
+                    return Err(format!(
+                        "invalid option '{}' in {}, expected key=value",
+                        option, parameter
+                    ));
+                };
+                let key = key.trim();
+                if !accept_extra && !specs.iter().any(|spec| spec.name == key) {
+                    let supported: Vec<String> = specs
+                        .iter()
+                        .map(|spec| format!("{} ({})", spec.name, spec.help))
+                        .collect();
+                    return Err(format!(
+                        "unknown option '{}' in {}, supported options: {}",
+                        key,
+                        parameter,
+                        supported.join(", ")
+                    ));
+                }
+                if options.iter().any(|(k, _)| k == key) {
+                    return Err(format!("option '{}' given more than once", key));
+                }
+                options.push((key.to_string(), value.trim().to_string()));
+            }
+        }
+        Ok(Self(options))
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Parses the value of an option, e.g. `options.parse_value::<u32>("cost")`.
+    pub fn parse_value<T>(&self, name: &str) -> Result<Option<T>, String>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        self.get(name)
+            .map(|value| {
+                value
+                    .parse()
+                    .map_err(|e| format!("invalid value '{}' for option '{}': {}", value, name, e))
+            })
+            .transpose()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.0.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SPECS: &[OptionSpec] = &[
+        OptionSpec {
+            name: "cost",
+            help: "work factor",
+        },
+        OptionSpec {
+            name: "encoding",
+            help: "hex or b64",
+        },
+    ];
+
+    #[test]
+    fn test_parse() {
+        let options = Options::parse(
+            &[
+                ("--bridge-parameter3", "cost=12; encoding=b64;"),
+                ("--bridge-parameter4", ""),
+            ],
+            SPECS,
+            false,
+        )
+        .unwrap();
+        assert_eq!(options.get("encoding"), Some("b64"));
+        assert_eq!(options.parse_value::<u32>("cost"), Ok(Some(12)));
+        assert_eq!(options.parse_value::<u32>("missing"), Ok(None));
+        assert!(options.parse_value::<u32>("encoding").is_err());
+        assert_eq!(options.iter().count(), 2);
+    }
+
+    #[test]
+    fn test_parse_extra() {
+        let sources = &[("--bridge-parameter3", "cost=12;pepper=@key.bin")];
+        let options = Options::parse(sources, SPECS, true).unwrap();
+        assert_eq!(options.get("cost"), Some("12"));
+        assert_eq!(options.get("pepper"), Some("@key.bin"));
+        assert!(Options::parse(sources, SPECS, false).is_err());
+        assert!(Options::parse(sources, &[], true).is_ok());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let err = Options::parse(&[("--bridge-parameter4", "rounds=5")], SPECS, false).unwrap_err();
+        assert_eq!(
+            err,
+            "unknown option 'rounds' in --bridge-parameter4, supported options: cost (work factor), encoding (hex or b64)"
+        );
+        assert!(Options::parse(&[("--bridge-parameter3", "cost")], SPECS, false).is_err());
+        assert!(Options::parse(&[("--bridge-parameter3", "=1")], SPECS, false).is_err());
+        assert!(Options::parse(
+            &[
+                ("--bridge-parameter3", "cost=1"),
+                ("--bridge-parameter4", "cost=2")
+            ],
+            SPECS,
+            false
+        )
+        .is_err());
+    }
+}
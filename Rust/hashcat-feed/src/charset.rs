@@ -0,0 +1,192 @@
+/**
+ * Author......: See docs/credits.txt
+ * License.....: MIT
+ */
+use std::{fs, path::Path, str};
+
+/// Number of custom charsets, `-1` to `-8`.
+pub const CUSTOM_CNT: usize = 8;
+
+/// Longest mask, in positions.
+pub const MASK_MAX: usize = 256;
+
+/// Characters of one mask position, without duplicates and in the order they
+/// were first named.
+pub type Charset = Vec<u8>;
+
+/// The built-in charset named by `?c`, as set up by `mp_setup_sys()`.
+fn builtin(c: u8) -> Option<Charset> {
+    let cs = match c {
+        b'l' => (b'a'..=b'z').collect(),
+        b'u' => (b'A'..=b'Z').collect(),
+        b'd' => (b'0'..=b'9').collect(),
+        b's' => (0x20..=0x7e)
+            .filter(|c: &u8| !c.is_ascii_alphanumeric())
+            .collect(),
+        b'a' => (0x20..=0x7e).collect(),
+        b'b' => (0x00..=0xff).collect(),
+        b'h' => (b'0'..=b'9').chain(b'a'..=b'f').collect(),
+        b'H' => (b'0'..=b'9').chain(b'A'..=b'F').collect(),
+        _ => return None,
+    };
+    Some(cs)
+}
+
+fn add(cs: &mut Charset, chars: &[u8]) {
+    for &c in chars {
+        if !cs.contains(&c) {
+            cs.push(c);
+        }
+    }
+}
+
+/// The custom charsets a mask may refer to as `?1` to `?8`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Charsets {
+    custom: [Option<Charset>; CUSTOM_CNT],
+}
+
+impl Charsets {
+    /// Defines custom charset `index` (0-based) from `value`, which is either
+    /// the path of a `.hcchr` file holding the raw characters or a charset in
+    /// mask syntax such as `?l?d_`. It may refer to the charsets defined
+    /// before it. An empty charset is an error, no candidate could be made
+    /// from it.
+    pub fn define(&mut self, index: usize, value: &[u8]) -> Result<(), String> {
+        let path = str::from_utf8(value)
+            .ok()
+            .filter(|p| Path::new(p).is_file());
+        let cs = if let Some(path) = path {
+            let buf = fs::read(path).map_err(|e| format!("{}: {}", path, e))?;
+            let len = buf.iter().rposition(|&c| c != b'\n' && c != b'\r');
+            let Some(len) = len else {
+                return Err(format!("{}: Custom charset file is empty.", path));
+            };
+            let mut cs = Charset::new();
+            add(&mut cs, &buf[..=len]);
+            cs
+        } else {
+            let mut cs = Charset::new();
+            for item in self.items(value)? {
+                add(&mut cs, &item);
+            }
+            cs
+        };
+        if cs.is_empty() {
+            return Err(format!("Custom-charset {} is empty.", index + 1));
+        }
+        self.custom[index] = Some(cs);
+        Ok(())
+    }
+
+    /// Expands `mask` into one charset per position.
+    pub fn parse(&self, mask: &[u8]) -> Result<Vec<Charset>, String> {
+        let css = self.items(mask)?;
+        if css.is_empty() {
+            return Err("Invalid mask length (0).".to_string());
+        }
+        if css.len() > MASK_MAX {
+            return Err(format!(
+                "Invalid mask length: {}",
+                String::from_utf8_lossy(mask)
+            ));
+        }
+        Ok(css
+            .into_iter()
+            .map(|item| {
+                let mut cs = Charset::new();
+                add(&mut cs, &item);
+                cs
+            })
+            .collect())
+    }
+
+    fn items(&self, buf: &[u8]) -> Result<Vec<Charset>, String> {
+        let syntax_error = || format!("Syntax error in mask: {}", String::from_utf8_lossy(buf));
+        let mut items = Vec::new();
+        let mut iter = buf.iter();
+        while let Some(&c) = iter.next() {
+            if c != b'?' {
+                items.push(vec![c]);
+                continue;
+            }
+            let &c = iter.next().ok_or_else(syntax_error)?;
+            let item = match c {
+                b'?' => vec![b'?'],
+                b'1'..=b'8' => {
+                    let index = (c - b'1') as usize;
+                    self.custom[index]
+                        .clone()
+                        .ok_or_else(|| format!("Custom-charset {} is undefined.", index + 1))?
+                }
+                _ => builtin(c).ok_or_else(syntax_error)?,
+            };
+            items.push(item);
+        }
+        Ok(items)
+    }
+}
+
+/// The number of positions of `mask`, counting `?x` as one.
+pub fn mask_len(mask: &[u8]) -> usize {
+    let mut len = 0;
+    let mut iter = mask.iter();
+    while let Some(&c) = iter.next() {
+        if c == b'?' {
+            iter.next();
+        }
+        len += 1;
+    }
+    len
+}
+
+/// The first `len` positions of `mask`.
+pub fn truncate_mask(mask: &[u8], len: usize) -> &[u8] {
+    let mut pos = 0;
+    for _ in 0..len {
+        if pos >= mask.len() {
+            break;
+        }
+        pos += if mask[pos] == b'?' { 2 } else { 1 };
+    }
+    &mask[..pos.min(mask.len())]
+}
+
+/// A line of a `.hcmask` file: up to eight custom charsets followed by the
+/// mask, separated by commas. A backslash escapes the next character, so `\,`
+/// is a literal comma.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MaskLine {
+    pub charsets: Vec<Vec<u8>>,
+    pub mask: Vec<u8>,
+}
+
+impl MaskLine {
+    pub fn parse(line: &[u8]) -> Result<Self, String> {
+        let mut fields = vec![Vec::new()];
+        let mut escaped = false;
+        for &c in line {
+            if escaped {
+                escaped = false;
+            } else if c == b'\\' {
+                escaped = true;
+                continue;
+            } else if c == b',' {
+                if fields.len() == CUSTOM_CNT + 1 {
+                    return Err(format!(
+                        "Invalid line '{}' in maskfile.",
+                        String::from_utf8_lossy(line)
+                    ));
+                }
+                fields.push(Vec::new());
+                continue;
+            }
+            fields.last_mut().unwrap().push(c);
+        }
+        let mask = fields.pop().unwrap();
+        Ok(Self {
+            charsets: fields,
+            mask,
+        })
+    }
+}
@@ -0,0 +1,417 @@
+/**
+ * Author......: See docs/credits.txt
+ * License.....: MIT
+ */
+use std::{
+    ffi::{c_char, c_int, c_void, CStr},
+    path::PathBuf,
+    ptr, slice,
+};
+
+use hashcat_sys::{event, generic_global_ctx_t, generic_thread_ctx_t, GENERIC_THREAD_NEXT_REJECT};
+
+use crate::{Feed, GlobalContext, PW_MAX, REJECTED};
+
+unsafe fn string_from_ptr(ptr: *const c_char) -> String {
+    if ptr.is_null() {
+        String::new()
+    } else {
+        unsafe { CStr::from_ptr(ptr).to_string_lossy().into_owned() }
+    }
+}
+
+/// Hands an error to hashcat, which prints `error_msg` once the call returns.
+/// Takes a raw pointer since the generator threads share `global_ctx`.
+unsafe fn set_error(global_ctx: *mut generic_global_ctx_t, err: &str) {
+    let global_ctx = unsafe { &mut *global_ctx };
+    let n = err.len().min(global_ctx.error_msg.len() - 1);
+    for (dst, &src) in global_ctx.error_msg.iter_mut().zip(&err.as_bytes()[..n]) {
+        *dst = src as c_char;
+    }
+    global_ctx.error_msg[n] = 0;
+    global_ctx.error = true;
+}
+
+/// What `thread_init` stores in `thrdata`.
+struct Unit<F: Feed> {
+    state: F::ThreadState,
+    /// The offset of the candidate the next `next` call returns.
+    pos: u64,
+}
+
+unsafe fn feed<'a, F: Feed>(global_ctx: &generic_global_ctx_t) -> Result<&'a F, String> {
+    let feed = global_ctx.gbldata as *const F;
+    if feed.is_null() {
+        return Err("global_init failed".to_string());
+    }
+    Ok(unsafe { &*feed })
+}
+
+unsafe fn unit<'a, F: Feed>(thread_ctx: &generic_thread_ctx_t) -> Result<&'a mut Unit<F>, String> {
+    let unit = thread_ctx.thrdata as *mut Unit<F>;
+    if unit.is_null() {
+        return Err("thread_init failed".to_string());
+    }
+    Ok(unsafe { &mut *unit })
+}
+
+/// # Safety
+///
+/// `global_ctx` must point to a valid `generic_global_ctx_t` whose `workv`
+/// holds `workc` strings, `hashcat_ctx` must be null or valid until
+/// `global_term`.
+pub unsafe fn global_init<F: Feed>(
+    global_ctx: *mut generic_global_ctx_t,
+    hashcat_ctx: *mut c_void,
+) -> bool {
+    assert!(!global_ctx.is_null());
+    let global_ctx = unsafe { &mut *global_ctx };
+    unsafe { event::init(hashcat_ctx, global_ctx.quiet) };
+
+    let workv: Vec<String> = if global_ctx.workv.is_null() {
+        vec![]
+    } else {
+        let workv = unsafe { slice::from_raw_parts(global_ctx.workv, global_ctx.workc as usize) };
+        workv
+            .iter()
+            .map(|&arg| unsafe { string_from_ptr(arg) })
+            .collect()
+    };
+    let (feed_name, args) = match workv.split_first() {
+        Some((feed_name, args)) => (feed_name.clone(), args.to_vec()),
+        None => (String::new(), vec![]),
+    };
+    let ctx = unsafe {
+        GlobalContext {
+            feed_name,
+            args,
+            quiet: global_ctx.quiet,
+            profile_dir: PathBuf::from(string_from_ptr(global_ctx.profile_dir)),
+            cache_dir: PathBuf::from(string_from_ptr(global_ctx.cache_dir)),
+            shared_dir: PathBuf::from(string_from_ptr(global_ctx.shared_dir)),
+        }
+    };
+
+    match F::global_init(&ctx) {
+        Ok(feed) => {
+            global_ctx.gbldata = Box::into_raw(Box::new(feed)) as *mut c_void;
+            true
+        }
+        Err(err) => {
+            unsafe { set_error(global_ctx, &err) };
+            false
+        }
+    }
+}
+
+/// # Safety
+///
+/// `global_ctx` must point to a valid `generic_global_ctx_t` whose `gbldata`
+/// was set by `global_init::<F>`.
+pub unsafe fn global_term<F: Feed>(global_ctx: *mut generic_global_ctx_t) {
+    assert!(!global_ctx.is_null());
+    let global_ctx = unsafe { &mut *global_ctx };
+    let feed = global_ctx.gbldata as *mut F;
+    if feed.is_null() {
+        return;
+    }
+    let feed = unsafe { Box::from_raw(feed) };
+    feed.global_term();
+    global_ctx.gbldata = ptr::null_mut();
+    event::reset();
+}
+
+/// # Safety
+///
+/// Same as `global_term`.
+pub unsafe fn global_keyspace<F: Feed>(global_ctx: *mut generic_global_ctx_t) -> u64 {
+    assert!(!global_ctx.is_null());
+    let global_ctx = unsafe { &mut *global_ctx };
+    match unsafe { feed::<F>(global_ctx) } {
+        Ok(feed) => feed.keyspace().unwrap_or(u64::MAX),
+        Err(err) => {
+            unsafe { set_error(global_ctx, &err) };
+            u64::MAX
+        }
+    }
+}
+
+/// # Safety
+///
+/// Same as `global_term`, and `thread_ctx` must point to a valid
+/// `generic_thread_ctx_t`.
+pub unsafe fn thread_init<F: Feed>(
+    global_ctx: *mut generic_global_ctx_t,
+    thread_ctx: *mut generic_thread_ctx_t,
+) -> bool {
+    assert!(!global_ctx.is_null());
+    assert!(!thread_ctx.is_null());
+    let feed = unsafe { feed::<F>(&*global_ctx) };
+    let thread_ctx = unsafe { &mut *thread_ctx };
+    let result = feed.and_then(|feed| feed.thread_init());
+    match result {
+        Ok(state) => {
+            let unit = Unit::<F> { state, pos: 0 };
+            thread_ctx.thrdata = Box::into_raw(Box::new(unit)) as *mut c_void;
+            true
+        }
+        Err(err) => {
+            unsafe { set_error(global_ctx, &err) };
+            false
+        }
+    }
+}
+
+/// # Safety
+///
+/// Same as `thread_init`, `thrdata` must be null or set by
+/// `thread_init::<F>`.
+pub unsafe fn thread_term<F: Feed>(
+    global_ctx: *mut generic_global_ctx_t,
+    thread_ctx: *mut generic_thread_ctx_t,
+) {
+    assert!(!global_ctx.is_null());
+    assert!(!thread_ctx.is_null());
+    let global_ctx = unsafe { &*global_ctx };
+    let thread_ctx = unsafe { &mut *thread_ctx };
+    let unit = thread_ctx.thrdata as *mut Unit<F>;
+    if unit.is_null() {
+        return;
+    }
+    let mut unit = unsafe { Box::from_raw(unit) };
+    if let Ok(feed) = unsafe { feed::<F>(global_ctx) } {
+        feed.thread_term(&mut unit.state);
+    }
+    thread_ctx.thrdata = ptr::null_mut();
+}
+
+/// # Safety
+///
+/// Same as `thread_term`.
+pub unsafe fn thread_seek<F: Feed>(
+    global_ctx: *mut generic_global_ctx_t,
+    thread_ctx: *mut generic_thread_ctx_t,
+    offset: u64,
+) -> bool {
+    assert!(!global_ctx.is_null());
+    assert!(!thread_ctx.is_null());
+    let feed = unsafe { feed::<F>(&*global_ctx) };
+    let thread_ctx = unsafe { &*thread_ctx };
+    let result = feed.and_then(|feed| {
+        let unit = unsafe { unit::<F>(thread_ctx) }?;
+        if unit.pos != offset {
+            feed.seek(&mut unit.state, offset)?;
+            unit.pos = offset;
+        }
+        Ok(())
+    });
+    match result {
+        Ok(()) => true,
+        Err(err) => {
+            unsafe { set_error(global_ctx, &err) };
+            false
+        }
+    }
+}
+
+/// # Safety
+///
+/// Same as `thread_term`, and `out_buf` must point to `PW_MAX` writable
+/// bytes.
+pub unsafe fn thread_next<F: Feed>(
+    global_ctx: *mut generic_global_ctx_t,
+    thread_ctx: *mut generic_thread_ctx_t,
+    out_buf: *mut u8,
+) -> c_int {
+    assert!(!global_ctx.is_null());
+    assert!(!thread_ctx.is_null());
+    assert!(!out_buf.is_null());
+    let feed = unsafe { feed::<F>(&*global_ctx) };
+    let thread_ctx = unsafe { &*thread_ctx };
+    let out = unsafe { &mut *(out_buf as *mut [u8; PW_MAX]) };
+    let result = feed.and_then(|feed| {
+        let unit = unsafe { unit::<F>(thread_ctx) }?;
+        let len = feed.next(&mut unit.state, out)?;
+        if len.is_some() {
+            unit.pos += 1;
+        }
+        Ok(len)
+    });
+    match result {
+        Ok(Some(REJECTED)) => GENERIC_THREAD_NEXT_REJECT,
+        Ok(Some(len)) => len.min(PW_MAX) as c_int,
+        Ok(None) => -1,
+        Err(err) => {
+            unsafe { set_error(global_ctx, &err) };
+            -1
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Counts from the first argument up to the second, rejecting multiples
+    /// of 13.
+    struct Count {
+        start: u64,
+        end: u64,
+    }
+
+    impl Feed for Count {
+        type ThreadState = (u64, usize);
+
+        fn global_init(ctx: &GlobalContext) -> Result<Self, String> {
+            let parse = |i: usize| -> Result<u64, String> {
+                let arg = ctx.args.get(i).ok_or("missing argument")?;
+                arg.parse().map_err(|_| format!("invalid number '{}'", arg))
+            };
+            Ok(Self {
+                start: parse(0)?,
+                end: parse(1)?,
+            })
+        }
+
+        fn keyspace(&self) -> Option<u64> {
+            Some(self.end - self.start)
+        }
+
+        fn thread_init(&self) -> Result<(u64, usize), String> {
+            Ok((self.start, 0))
+        }
+
+        fn seek(&self, state: &mut (u64, usize), offset: u64) -> Result<(), String> {
+            state.0 = self.start + offset;
+            state.1 += 1;
+            Ok(())
+        }
+
+        fn next(
+            &self,
+            state: &mut (u64, usize),
+            out: &mut [u8; PW_MAX],
+        ) -> Result<Option<usize>, String> {
+            if state.0 >= self.end {
+                return Ok(None);
+            }
+            if state.0.is_multiple_of(13) {
+                state.0 += 1;
+                return Ok(Some(REJECTED));
+            }
+            let word = state.0.to_string();
+            out[..word.len()].copy_from_slice(word.as_bytes());
+            state.0 += 1;
+            Ok(Some(word.len()))
+        }
+    }
+
+    fn global_ctx(workv: &mut [*mut c_char]) -> generic_global_ctx_t {
+        let mut global_ctx: generic_global_ctx_t = unsafe { std::mem::zeroed() };
+        global_ctx.workc = workv.len() as c_int;
+        global_ctx.workv = workv.as_mut_ptr();
+        global_ctx
+    }
+
+    fn error_msg(global_ctx: &generic_global_ctx_t) -> String {
+        unsafe { string_from_ptr(global_ctx.error_msg.as_ptr()) }
+    }
+
+    #[test]
+    fn test_feed_abi() {
+        let mut workv =
+            [c"count".as_ptr(), c"10".as_ptr(), c"13".as_ptr()].map(|p| p as *mut c_char);
+        let mut global_ctx = global_ctx(&mut workv);
+        let mut thread_ctx: generic_thread_ctx_t = unsafe { std::mem::zeroed() };
+        let mut out = [0u8; PW_MAX];
+        let mut next = |global_ctx: &mut generic_global_ctx_t,
+                        thread_ctx: &mut generic_thread_ctx_t| {
+            let len = unsafe { thread_next::<Count>(global_ctx, thread_ctx, out.as_mut_ptr()) };
+            (len >= 0).then(|| String::from_utf8_lossy(&out[..len as usize]).into_owned())
+        };
+
+        unsafe {
+            assert!(global_init::<Count>(&mut global_ctx, ptr::null_mut()));
+            assert_eq!(global_keyspace::<Count>(&mut global_ctx), 3);
+            assert!(thread_init::<Count>(&mut global_ctx, &mut thread_ctx));
+
+            assert!(thread_seek::<Count>(&mut global_ctx, &mut thread_ctx, 0));
+            assert_eq!(
+                next(&mut global_ctx, &mut thread_ctx).as_deref(),
+                Some("10")
+            );
+            assert!(thread_seek::<Count>(&mut global_ctx, &mut thread_ctx, 1));
+            assert_eq!(
+                next(&mut global_ctx, &mut thread_ctx).as_deref(),
+                Some("11")
+            );
+            // Seeking to the current position doesn't reach the feed.
+            assert_eq!((*(thread_ctx.thrdata as *const Unit<Count>)).state.1, 0);
+
+            assert!(thread_seek::<Count>(&mut global_ctx, &mut thread_ctx, 0));
+            assert_eq!(
+                next(&mut global_ctx, &mut thread_ctx).as_deref(),
+                Some("10")
+            );
+            assert!(thread_seek::<Count>(&mut global_ctx, &mut thread_ctx, 2));
+            assert_eq!(
+                next(&mut global_ctx, &mut thread_ctx).as_deref(),
+                Some("12")
+            );
+            assert_eq!(next(&mut global_ctx, &mut thread_ctx), None);
+            assert!(!global_ctx.error);
+
+            thread_term::<Count>(&mut global_ctx, &mut thread_ctx);
+            global_term::<Count>(&mut global_ctx);
+
+            thread_term::<Count>(&mut global_ctx, &mut thread_ctx);
+            assert!(thread_ctx.thrdata.is_null());
+            global_term::<Count>(&mut global_ctx);
+            assert!(global_ctx.gbldata.is_null());
+        }
+    }
+
+    #[test]
+    fn test_feed_reject() {
+        let mut workv =
+            [c"count".as_ptr(), c"12".as_ptr(), c"15".as_ptr()].map(|p| p as *mut c_char);
+        let mut global_ctx = global_ctx(&mut workv);
+        let mut thread_ctx: generic_thread_ctx_t = unsafe { std::mem::zeroed() };
+        let mut out = [0u8; PW_MAX];
+        unsafe {
+            assert!(global_init::<Count>(&mut global_ctx, ptr::null_mut()));
+            assert!(thread_init::<Count>(&mut global_ctx, &mut thread_ctx));
+
+            let lens: Vec<c_int> = (0..4)
+                .map(|_| thread_next::<Count>(&mut global_ctx, &mut thread_ctx, out.as_mut_ptr()))
+                .collect();
+            assert_eq!(lens, [2, GENERIC_THREAD_NEXT_REJECT, 2, -1]);
+            assert_eq!(&out[..2], b"14");
+            // The rejected candidate still took its offset.
+            assert_eq!((*(thread_ctx.thrdata as *const Unit<Count>)).pos, 3);
+
+            thread_term::<Count>(&mut global_ctx, &mut thread_ctx);
+            global_term::<Count>(&mut global_ctx);
+        }
+    }
+
+    #[test]
+    fn test_feed_errors() {
+        let mut workv =
+            [c"count".as_ptr(), c"ten".as_ptr(), c"13".as_ptr()].map(|p| p as *mut c_char);
+        let mut global_ctx = global_ctx(&mut workv);
+        let mut thread_ctx: generic_thread_ctx_t = unsafe { std::mem::zeroed() };
+        unsafe {
+            assert!(!global_init::<Count>(&mut global_ctx, ptr::null_mut()));
+            assert!(global_ctx.error);
+            assert_eq!(error_msg(&global_ctx), "invalid number 'ten'");
+
+            assert!(!thread_init::<Count>(&mut global_ctx, &mut thread_ctx));
+            assert_eq!(error_msg(&global_ctx), "global_init failed");
+        }
+
+        let mut global_ctx: generic_global_ctx_t = unsafe { std::mem::zeroed() };
+        unsafe { set_error(&mut global_ctx, &"x".repeat(300)) };
+        assert_eq!(error_msg(&global_ctx).len(), 255);
+    }
+}
@@ -0,0 +1,179 @@
+/**
+ * Author......: See docs/credits.txt
+ * License.....: MIT
+ */
+use std::path::PathBuf;
+
+pub mod charset;
+#[doc(hidden)]
+pub mod export;
+/// Helpers for the tests of feed crates, enabled by the `testing` feature.
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+pub mod wordlist;
+
+pub use hashcat_sys::{
+    self, event, generic_plugin_options_t, GENERIC_PLUGIN_OPTIONS_AUTOHEX,
+    GENERIC_PLUGIN_OPTIONS_ICONV, GENERIC_PLUGIN_OPTIONS_RULES, GENERIC_PLUGIN_OPTIONS_UNDEFINED,
+};
+
+pub use wordlist::Wordlist;
+
+/// Maximum length of a password candidate.
+pub const PW_MAX: usize = 256;
+
+/// Length returned by [`Feed::next`] for a candidate the feed rejected
+/// itself. Hashcat counts it like the candidates it rejects, so the offset
+/// still advances by one.
+pub const REJECTED: usize = usize::MAX;
+
+/// Parameters available when the feed is loaded.
+pub struct GlobalContext {
+    /// The feed as given on the command line, `workv[0]`.
+    pub feed_name: String,
+    /// The `-a 8` arguments following the feed name.
+    pub args: Vec<String>,
+
+    /// Set by `--quiet`, which [`event::info`] and [`event::warning`] already
+    /// honour.
+    pub quiet: bool,
+
+    pub profile_dir: PathBuf,
+    pub cache_dir: PathBuf,
+    /// Where hashcat keeps its shared files, e.g. `hashcat.hcstat2`.
+    pub shared_dir: PathBuf,
+}
+
+/// A password candidate generator for attack mode 8. One instance is created
+/// by `global_init` and shared by the generator threads of all compute
+/// devices, each of which keeps its own `ThreadState`.
+///
+/// Hashcat splits the keyspace between the threads by seeking each of them to
+/// the start of its next batch, so `seek` and `next` have to agree on the
+/// order of the candidates. Errors are reported to hashcat, which prints them
+/// and stops the session.
+pub trait Feed: Sized + Send + Sync + 'static {
+    /// State owned by a single generator thread, e.g. the current position.
+    type ThreadState: Send;
+
+    /// The post-processing hashcat may apply to the candidates, a
+    /// combination of the `GENERIC_PLUGIN_OPTIONS_*` flags.
+    const PLUGIN_OPTIONS: generic_plugin_options_t = GENERIC_PLUGIN_OPTIONS_UNDEFINED;
+
+    fn global_init(ctx: &GlobalContext) -> Result<Self, String>;
+
+    fn global_term(&self) {}
+
+    /// The number of candidates, or `None` if it is unknown. Hashcat shows
+    /// no progress or ETA in that case.
+    fn keyspace(&self) -> Option<u64> {
+        None
+    }
+
+    /// Creates the state of a thread, positioned at the first candidate.
+    fn thread_init(&self) -> Result<Self::ThreadState, String>;
+
+    #[allow(unused_variables)]
+    fn thread_term(&self, state: &mut Self::ThreadState) {}
+
+    /// Positions the thread at the absolute candidate `offset`. It is only
+    /// called when the offset differs from the position reached by `next`.
+    fn seek(&self, state: &mut Self::ThreadState, offset: u64) -> Result<(), String>;
+
+    /// Writes the next candidate into `out` and returns its length, or `None`
+    /// once the keyspace is exhausted. Every call consumes one offset; return
+    /// [`REJECTED`] when the candidate at that offset is skipped.
+    fn next(
+        &self,
+        state: &mut Self::ThreadState,
+        out: &mut [u8; PW_MAX],
+    ) -> Result<Option<usize>, String>;
+}
+
+/// Generates the C functions and symbols hashcat expects from a feed library
+/// for a type implementing [`Feed`]. Use it once per crate.
+#[macro_export]
+macro_rules! export_feed {
+    ($feed:ty) => {
+        const _: () = {
+            use ::std::ffi::{c_int, c_void};
+
+            use $crate::{
+                export,
+                hashcat_sys::{
+                    generic_global_ctx_t, generic_plugin_options_t, generic_thread_ctx_t,
+                    GENERIC_PLUGIN_VERSION_REQ,
+                },
+                Feed,
+            };
+
+            #[unsafe(no_mangle)]
+            pub static GENERIC_PLUGIN_VERSION: c_int = GENERIC_PLUGIN_VERSION_REQ as c_int;
+
+            #[unsafe(no_mangle)]
+            pub static GENERIC_PLUGIN_OPTIONS: generic_plugin_options_t =
+                <$feed as Feed>::PLUGIN_OPTIONS;
+
+            #[unsafe(no_mangle)]
+            pub extern "C" fn global_init(
+                global_ctx: *mut generic_global_ctx_t,
+                _thread_ctx: *mut *mut generic_thread_ctx_t,
+                hashcat_ctx: *mut c_void,
+            ) -> bool {
+                unsafe { export::global_init::<$feed>(global_ctx, hashcat_ctx) }
+            }
+
+            #[unsafe(no_mangle)]
+            pub extern "C" fn global_term(
+                global_ctx: *mut generic_global_ctx_t,
+                _thread_ctx: *mut *mut generic_thread_ctx_t,
+                _hashcat_ctx: *mut c_void,
+            ) {
+                unsafe { export::global_term::<$feed>(global_ctx) }
+            }
+
+            #[unsafe(no_mangle)]
+            pub extern "C" fn global_keyspace(
+                global_ctx: *mut generic_global_ctx_t,
+                _thread_ctx: *mut *mut generic_thread_ctx_t,
+                _hashcat_ctx: *mut c_void,
+            ) -> u64 {
+                unsafe { export::global_keyspace::<$feed>(global_ctx) }
+            }
+
+            #[unsafe(no_mangle)]
+            pub extern "C" fn thread_init(
+                global_ctx: *mut generic_global_ctx_t,
+                thread_ctx: *mut generic_thread_ctx_t,
+            ) -> bool {
+                unsafe { export::thread_init::<$feed>(global_ctx, thread_ctx) }
+            }
+
+            #[unsafe(no_mangle)]
+            pub extern "C" fn thread_term(
+                global_ctx: *mut generic_global_ctx_t,
+                thread_ctx: *mut generic_thread_ctx_t,
+            ) {
+                unsafe { export::thread_term::<$feed>(global_ctx, thread_ctx) }
+            }
+
+            #[unsafe(no_mangle)]
+            pub extern "C" fn thread_seek(
+                global_ctx: *mut generic_global_ctx_t,
+                thread_ctx: *mut generic_thread_ctx_t,
+                offset: u64,
+            ) -> bool {
+                unsafe { export::thread_seek::<$feed>(global_ctx, thread_ctx, offset) }
+            }
+
+            #[unsafe(no_mangle)]
+            pub extern "C" fn thread_next(
+                global_ctx: *mut generic_global_ctx_t,
+                thread_ctx: *mut generic_thread_ctx_t,
+                out_buf: *mut u8,
+            ) -> c_int {
+                unsafe { export::thread_next::<$feed>(global_ctx, thread_ctx, out_buf) }
+            }
+        };
+    };
+}
@@ -0,0 +1,33 @@
+/**
+ * Author......: See docs/credits.txt
+ * License.....: MIT
+ */
+use std::{env, fs, path::PathBuf, process};
+
+use crate::{Feed, PW_MAX, REJECTED};
+
+/// The candidates of `count` offsets starting at `offset`, read the way
+/// hashcat does: one seek, then `next` until the batch or the keyspace ends.
+/// Rejected candidates take their offset but are left out.
+pub fn candidates<F: Feed>(feed: &F, offset: u64, count: usize) -> Vec<String> {
+    let mut state = feed.thread_init().unwrap();
+    feed.seek(&mut state, offset).unwrap();
+    let mut out = [0u8; PW_MAX];
+    let mut words = Vec::new();
+    for _ in 0..count {
+        match feed.next(&mut state, &mut out).unwrap() {
+            None => break,
+            Some(REJECTED) => {}
+            Some(len) => words.push(String::from_utf8_lossy(&out[..len]).into_owned()),
+        }
+    }
+    words
+}
+
+/// Writes `content` to a file in the temporary directory that is unique to
+/// the test process and returns its path.
+pub fn temp_file(name: &str, content: impl AsRef<[u8]>) -> PathBuf {
+    let path = env::temp_dir().join(format!("hashcat_feed_{}_{}", process::id(), name));
+    fs::write(&path, content).unwrap();
+    path
+}
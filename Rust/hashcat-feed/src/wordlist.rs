@@ -0,0 +1,104 @@
+/**
+ * Author......: See docs/credits.txt
+ * License.....: MIT
+ */
+use std::{borrow::Cow, fs, path::Path};
+
+/// A wordlist held in memory with an index of its lines, so feeds can seek to
+/// any word directly. Lines are split like `feed_wordlist` does: on `\n`, with
+/// trailing `\r` removed and empty lines kept as empty words.
+pub struct Wordlist {
+    data: Vec<u8>,
+    /// Start of each line, followed by the end of the data plus one.
+    starts: Vec<usize>,
+}
+
+impl Wordlist {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        let data = fs::read(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Ok(Self::from_bytes(data))
+    }
+
+    pub fn from_bytes(data: Vec<u8>) -> Self {
+        let mut starts = vec![0];
+        starts.extend(
+            data.iter()
+                .enumerate()
+                .filter(|&(_, &c)| c == b'\n')
+                .map(|(pos, _)| pos + 1),
+        );
+        if data.last().is_some_and(|&c| c != b'\n') {
+            starts.push(data.len() + 1);
+        }
+        Self { data, starts }
+    }
+
+    pub fn len(&self) -> usize {
+        self.starts.len() - 1
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The line `index` as it is in the file, without the line ending.
+    pub fn line(&self, index: usize) -> &[u8] {
+        let line = &self.data[self.starts[index]..self.starts[index + 1] - 1];
+        let len = line
+            .iter()
+            .rposition(|&c| c != b'\r')
+            .map_or(0, |pos| pos + 1);
+        &line[..len]
+    }
+
+    /// The word `index`, with the `$HEX[...]` notation decoded.
+    pub fn word(&self, index: usize) -> Cow<'_, [u8]> {
+        let line = self.line(index);
+        match unhex(line) {
+            Some(word) => Cow::Owned(word),
+            None => Cow::Borrowed(line),
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Cow<'_, [u8]>> {
+        (0..self.len()).map(|index| self.word(index))
+    }
+}
+
+/// Decodes a word in `$HEX[...]` notation, like `is_hexify()` and
+/// `exec_unhexify()` in hashcat.
+pub fn unhex(word: &[u8]) -> Option<Vec<u8>> {
+    let hex = word.strip_prefix(b"$HEX[")?.strip_suffix(b"]")?;
+    if hex.len() % 2 != 0 || !hex.iter().all(u8::is_ascii_hexdigit) {
+        return None;
+    }
+    let digit = |c: u8| (c as char).to_digit(16).unwrap() as u8;
+    Some(
+        hex.chunks(2)
+            .map(|pair| digit(pair[0]) << 4 | digit(pair[1]))
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lines() {
+        let wordlist = Wordlist::from_bytes(b"one\r\n\ntwo\n$HEX[41G2]\n$HEX[4142]\nlast".to_vec());
+        let words: Vec<_> = wordlist.iter().collect();
+        assert_eq!(
+            words,
+            [&b"one"[..], b"", b"two", b"$HEX[41G2]", b"AB", b"last"]
+        );
+
+        assert_eq!(Wordlist::from_bytes(b"a\nb\n".to_vec()).len(), 2);
+        assert!(Wordlist::from_bytes(Vec::new()).is_empty());
+
+        assert_eq!(unhex(b"$HEX[]"), Some(Vec::new()));
+        assert_eq!(unhex(b"$HEX[4]"), None);
+        assert_eq!(unhex(b"$HEX[+1]"), None);
+    }
+}
@@ -1,6 +1,8 @@
 fn main() {
     bindgen::Builder::default()
         .header("src/hashcat_types.h")
+        .clang_arg("-I../../include")
+        .prepend_enum_name(false)
         .generate()
         .expect("Unable to generate bindings")
         .write_to_file("src/bindings.rs")
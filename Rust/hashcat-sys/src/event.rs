@@ -0,0 +1,98 @@
+/**
+ * Author......: See docs/credits.txt
+ * License.....: MIT
+ */
+use std::{
+    ffi::c_void,
+    ptr,
+    sync::{Mutex, MutexGuard},
+};
+
+use crate::bindings::{
+    hashcat_ctx_t, EVENT_LOG_ERROR, EVENT_LOG_INFO, EVENT_LOG_WARNING, HCBUFSIZ_SMALL,
+};
+
+struct Target {
+    hashcat_ctx: *mut hashcat_ctx_t,
+    quiet: bool,
+}
+
+// The context is only used while holding the lock.
+unsafe impl Send for Target {}
+
+static TARGET: Mutex<Target> = Mutex::new(Target {
+    hashcat_ctx: ptr::null_mut(),
+    quiet: false,
+});
+
+fn target() -> MutexGuard<'static, Target> {
+    TARGET.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Routes the messages of this library through the event system of
+/// `hashcat_ctx`, so API frontends receive them, or to stderr if it is null.
+/// Info and warning messages are dropped when `quiet` is set.
+///
+/// # Safety
+///
+/// `hashcat_ctx` must be null or point to the `hashcat_ctx_t` hashcat passed
+/// to the plugin, and stay valid until the next `init` or `reset` call.
+pub unsafe fn init(hashcat_ctx: *mut c_void, quiet: bool) {
+    *target() = Target {
+        hashcat_ctx: hashcat_ctx as *mut hashcat_ctx_t,
+        quiet,
+    };
+}
+
+/// Goes back to printing to stderr, e.g. before the context is released.
+pub fn reset() {
+    *target() = Target {
+        hashcat_ctx: ptr::null_mut(),
+        quiet: false,
+    };
+}
+
+pub fn info(msg: &str) {
+    log(EVENT_LOG_INFO, "", msg);
+}
+
+pub fn warning(msg: &str) {
+    log(EVENT_LOG_WARNING, "WARNING: ", msg);
+}
+
+/// Errors are shown even with `--quiet`.
+pub fn error(msg: &str) {
+    log(EVENT_LOG_ERROR, "ERROR: ", msg);
+}
+
+fn log(id: u32, prefix: &str, msg: &str) {
+    let target = target();
+    if target.quiet && id != EVENT_LOG_ERROR {
+        return;
+    }
+    if target.hashcat_ctx.is_null() || !unsafe { emit(target.hashcat_ctx, id, msg) } {
+        eprintln!("{}{}", prefix, msg);
+    }
+}
+
+/// Does what `event_log_info()` and friends do in C. Returns false if the
+/// context has no event system.
+unsafe fn emit(hashcat_ctx: *mut hashcat_ctx_t, id: u32, msg: &str) -> bool {
+    let (event, event_ctx) = unsafe { ((*hashcat_ctx).event, (*hashcat_ctx).event_ctx) };
+    let Some(event) = event else {
+        return false;
+    };
+    if event_ctx.is_null() {
+        return false;
+    }
+    let len = msg.len().min(HCBUFSIZ_SMALL as usize - 1);
+    unsafe {
+        let buf = ptr::addr_of_mut!((*event_ctx).msg_buf) as *mut u8;
+        ptr::copy_nonoverlapping(msg.as_ptr(), buf, len);
+        *buf.add(len) = 0;
+        (*event_ctx).msg_len = len;
+        (*event_ctx).msg_newline = true;
+        event(id, hashcat_ctx, ptr::null(), 0);
+    }
+    true
+}
@@ -0,0 +1,78 @@
+/**
+ * Author......: See docs/credits.txt
+ * License.....: MIT
+ */
+use std::{mem, slice};
+
+use crate::bindings::{generic_io_t, generic_io_tmp_t, u32_};
+
+/// Views the first `len` bytes of a `u32` buffer, clamped to its size so that
+/// a corrupt length can't read past the end.
+fn as_bytes(buf: &[u32_], len: u32_) -> &[u8] {
+    let len = (len as usize).min(mem::size_of_val(buf));
+    unsafe { slice::from_raw_parts(buf.as_ptr() as *const u8, len) }
+}
+
+impl generic_io_tmp_t {
+    /// Maximum number of outputs per candidate.
+    pub const MAX_OUTPUTS: usize = 32;
+    /// Maximum size of a single output in bytes.
+    pub const MAX_OUTPUT_LEN: usize = 256;
+
+    /// The password candidate.
+    pub fn password(&self) -> &[u8] {
+        as_bytes(&self.pw_buf, self.pw_len)
+    }
+
+    /// The outputs pushed so far.
+    pub fn outputs(&self) -> impl Iterator<Item = &[u8]> {
+        let cnt = (self.out_cnt as usize).min(Self::MAX_OUTPUTS);
+        self.out_buf[..cnt]
+            .iter()
+            .zip(&self.out_len)
+            .map(|(buf, &len)| as_bytes(buf, len))
+    }
+
+    /// Removes all outputs. Hashcat doesn't reset them between batches.
+    pub fn clear_outputs(&mut self) {
+        self.out_cnt = 0;
+    }
+
+    /// Appends an output, which hashcat compares with the stored hash.
+    pub fn push_output(&mut self, output: &[u8]) -> Result<(), String> {
+        let idx = self.out_cnt as usize;
+        if idx >= Self::MAX_OUTPUTS {
+            return Err(format!(
+                "too many outputs, at most {} are allowed",
+                Self::MAX_OUTPUTS
+            ));
+        }
+        if output.len() > Self::MAX_OUTPUT_LEN {
+            return Err(format!(
+                "output of {} bytes exceeds the limit of {} bytes",
+                output.len(),
+                Self::MAX_OUTPUT_LEN
+            ));
+        }
+        let buf = &mut self.out_buf[idx];
+        let buf = unsafe {
+            slice::from_raw_parts_mut(buf.as_mut_ptr() as *mut u8, mem::size_of_val(buf))
+        };
+        buf[..output.len()].copy_from_slice(output);
+        self.out_len[idx] = output.len() as u32_;
+        self.out_cnt += 1;
+        Ok(())
+    }
+}
+
+impl generic_io_t {
+    /// The salt part of the hash line, everything after the first `*`.
+    pub fn salt(&self) -> &[u8] {
+        as_bytes(&self.salt_buf, self.salt_len)
+    }
+
+    /// The hash part of the hash line, everything before the first `*`.
+    pub fn stored_hash(&self) -> &[u8] {
+        as_bytes(&self.hash_buf, self.hash_len)
+    }
+}
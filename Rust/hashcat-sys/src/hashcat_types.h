@@ -3,9 +3,15 @@
  * License.....: MIT
  */
 
+#include <stdbool.h>
+#include <stddef.h>
 #include <stdint.h>
 
+#include "generic_layout.h"
+
+typedef uint8_t u8;
 typedef uint32_t u32;
+typedef uint64_t u64;
 typedef void unit_t;
 typedef void *hc_dynlib_t;
 typedef void *RS_GET_INFO;
@@ -101,3 +107,129 @@ typedef struct bridge_context
     const char *bridge_parameter4;
 
 } bridge_context_t;
+
+// Sync with:
+// include/generic.h
+// include/types.h
+
+#define GENERIC_PLUGIN_VERSION_REQ 713
+
+#define GENERIC_THREAD_NEXT_REJECT -2
+
+typedef enum generic_plugin_options
+{
+    GENERIC_PLUGIN_OPTIONS_AUTOHEX   = 1 << 0,
+    GENERIC_PLUGIN_OPTIONS_ICONV     = 1 << 1,
+    GENERIC_PLUGIN_OPTIONS_RULES     = 1 << 2,
+
+    GENERIC_PLUGIN_OPTIONS_UNDEFINED = 0,
+
+} generic_plugin_options_t;
+
+typedef struct generic_global_ctx
+{
+    bool   quiet;
+
+    int    workc;
+    char **workv;
+
+    char  *profile_dir;
+    char  *cache_dir;
+
+    bool   error;
+    char   error_msg[256];
+
+    void  *gbldata; // super generic
+
+    char  *shared_dir;
+
+} generic_global_ctx_t;
+
+typedef struct generic_thread_ctx
+{
+    void  *thrdata; // super generic
+
+} generic_thread_ctx_t;
+
+typedef bool (*GENERIC_GLOBAL_INIT)     (generic_global_ctx_t *, generic_thread_ctx_t **, void *);
+typedef void (*GENERIC_GLOBAL_TERM)     (generic_global_ctx_t *, generic_thread_ctx_t **, void *);
+typedef u64  (*GENERIC_GLOBAL_KEYSPACE) (generic_global_ctx_t *, generic_thread_ctx_t **, void *);
+
+typedef bool (*GENERIC_THREAD_INIT)     (generic_global_ctx_t *, generic_thread_ctx_t *);
+typedef void (*GENERIC_THREAD_TERM)     (generic_global_ctx_t *, generic_thread_ctx_t *);
+typedef int  (*GENERIC_THREAD_NEXT)     (generic_global_ctx_t *, generic_thread_ctx_t *, u8 *);
+typedef bool (*GENERIC_THREAD_SEEK)     (generic_global_ctx_t *, generic_thread_ctx_t *, const u64);
+
+// Sync with:
+// include/common.h
+// include/types.h
+//
+// Only the parts plugins need to emit log events. Both structures are only
+// accessed through the pointer hashcat passes to feeds, the fields past the
+// ones used here are left out.
+
+#define HCBUFSIZ_SMALL 0x2000
+#define HCBUFSIZ_LARGE 0x1000000
+
+#define MAX_OLD_EVENTS 10
+
+typedef enum event_identifier
+{
+    EVENT_LOG_ERROR                 = 0x00000080,
+    EVENT_LOG_INFO                  = 0x00000081,
+    EVENT_LOG_WARNING               = 0x00000082,
+    EVENT_LOG_ADVICE                = 0x00000083,
+
+} event_identifier_t;
+
+typedef struct event_ctx
+{
+    char   old_buf[MAX_OLD_EVENTS][HCBUFSIZ_LARGE];
+    size_t old_len[MAX_OLD_EVENTS];
+    int    old_cnt;
+
+    char   msg_buf[HCBUFSIZ_LARGE];
+    size_t msg_len;
+    bool   msg_newline;
+
+    size_t prev_len;
+
+} event_ctx_t;
+
+typedef struct hashcat_ctx
+{
+    void *brain_ctx;
+    void *bitmap_ctx;
+    void *bridge_ctx;
+    void *combinator_ctx;
+    void *cpt_ctx;
+    void *debugfile_ctx;
+    void *dictstat_ctx;
+    event_ctx_t *event_ctx;
+    void *folder_config;
+    void *generic_ctx;
+    void *hashcat_user;
+    void *hashconfig;
+    void *hashes;
+    void *hwmon_ctx;
+    void *induct_ctx;
+    void *logfile_ctx;
+    void *loopback_ctx;
+    void *mask_ctx;
+    void *module_ctx;
+    void *backend_ctx;
+    void *outcheck_ctx;
+    void *outfile_ctx;
+    void *pidfile_ctx;
+    void *potfile_ctx;
+    void *restore_ctx;
+    void *status_ctx;
+    void *straight_ctx;
+    void *tuning_db;
+    void *user_options_extra;
+    void *user_options;
+    void *wl_data;
+
+    void (*event) (const u32, struct hashcat_ctx *, const void *, const size_t);
+
+} hashcat_ctx_t;
@@ -0,0 +1,77 @@
+/**
+ * Author......: See docs/credits.txt
+ * License.....: MIT
+ */
+use std::mem::{align_of, offset_of, size_of};
+
+use crate::bindings::*;
+
+// Hashcat and compiled plugins share these structures, so a change to them
+// has to be a deliberate ABI break. The expected layout is kept in
+// include/generic_layout.h, which src/generic.c checks against
+// include/types.h. These checks compare the copy in hashcat_types.h with the
+// same values, so the build fails on whichever side drifts.
+
+#[cfg(target_pointer_width = "64")]
+const _: () = {
+    const fn is(value: usize, expected: u32) -> bool {
+        value == expected as usize
+    }
+
+    type G = generic_global_ctx_t;
+    assert!(is(size_of::<G>(), LAYOUT_GENERIC_GLOBAL_CTX_SIZE));
+    assert!(is(align_of::<G>(), LAYOUT_GENERIC_GLOBAL_CTX_ALIGN));
+    assert!(is(offset_of!(G, quiet), LAYOUT_GENERIC_GLOBAL_CTX_QUIET));
+    assert!(is(offset_of!(G, workc), LAYOUT_GENERIC_GLOBAL_CTX_WORKC));
+    assert!(is(offset_of!(G, workv), LAYOUT_GENERIC_GLOBAL_CTX_WORKV));
+    assert!(is(
+        offset_of!(G, profile_dir),
+        LAYOUT_GENERIC_GLOBAL_CTX_PROFILE_DIR
+    ));
+    assert!(is(
+        offset_of!(G, cache_dir),
+        LAYOUT_GENERIC_GLOBAL_CTX_CACHE_DIR
+    ));
+    assert!(is(offset_of!(G, error), LAYOUT_GENERIC_GLOBAL_CTX_ERROR));
+    assert!(is(
+        offset_of!(G, error_msg),
+        LAYOUT_GENERIC_GLOBAL_CTX_ERROR_MSG
+    ));
+    assert!(is(
+        offset_of!(G, gbldata),
+        LAYOUT_GENERIC_GLOBAL_CTX_GBLDATA
+    ));
+    assert!(is(
+        offset_of!(G, shared_dir),
+        LAYOUT_GENERIC_GLOBAL_CTX_SHARED_DIR
+    ));
+
+    type T = generic_thread_ctx_t;
+    assert!(is(size_of::<T>(), LAYOUT_GENERIC_THREAD_CTX_SIZE));
+    assert!(is(
+        offset_of!(T, thrdata),
+        LAYOUT_GENERIC_THREAD_CTX_THRDATA
+    ));
+
+    assert!(is(
+        offset_of!(hashcat_ctx_t, event_ctx),
+        LAYOUT_HASHCAT_CTX_EVENT_CTX
+    ));
+    assert!(is(
+        offset_of!(hashcat_ctx_t, event),
+        LAYOUT_HASHCAT_CTX_EVENT
+    ));
+
+    assert!(is(
+        offset_of!(event_ctx_t, msg_buf),
+        LAYOUT_EVENT_CTX_MSG_BUF
+    ));
+    assert!(is(
+        offset_of!(event_ctx_t, msg_len),
+        LAYOUT_EVENT_CTX_MSG_LEN
+    ));
+    assert!(is(
+        offset_of!(event_ctx_t, msg_newline),
+        LAYOUT_EVENT_CTX_MSG_NEWLINE
+    ));
+};
@@ -6,6 +6,9 @@
 )]
 #[rustfmt::skip]
 pub mod bindings;
+pub mod event;
+mod generic_io;
+mod layout;
 
 pub use bindings::*;
 
@@ -25,4 +28,101 @@ mod test {
         assert_eq!(ctx.hash_len, 0);
         assert_eq!(ctx.salt_len, 0);
     }
+
+    #[test]
+    fn test_generic_plugin_abi() {
+        assert_eq!(GENERIC_PLUGIN_VERSION_REQ, 713);
+        assert_eq!(GENERIC_PLUGIN_OPTIONS_AUTOHEX, 1);
+        assert_eq!(GENERIC_PLUGIN_OPTIONS_ICONV, 2);
+        assert_eq!(GENERIC_PLUGIN_OPTIONS_RULES, 4);
+        assert_eq!(GENERIC_PLUGIN_OPTIONS_UNDEFINED, 0);
+        assert_eq!(
+            std::mem::size_of::<generic_plugin_options_t>(),
+            std::mem::size_of::<std::ffi::c_int>()
+        );
+    }
+
+    #[test]
+    fn test_generic_io() {
+        let mut io: generic_io_tmp_t = unsafe { std::mem::zeroed() };
+        io.pw_buf[0] = u32::from_ne_bytes(*b"hash");
+        io.pw_buf[1] = u32::from_ne_bytes(*b"cat\0");
+        io.pw_len = 7;
+        assert_eq!(io.password(), b"hashcat");
+        io.pw_len = u32::MAX;
+        assert_eq!(io.password().len(), 256);
+
+        io.out_cnt = 5;
+        io.clear_outputs();
+        io.push_output(b"abc").unwrap();
+        io.push_output(&[0xff; 256]).unwrap();
+        assert!(io.push_output(&[0; 257]).is_err());
+        let outputs: Vec<&[u8]> = io.outputs().collect();
+        assert_eq!(outputs, [&b"abc"[..], &[0xff; 256][..]]);
+        for _ in 2..32 {
+            io.push_output(b"").unwrap();
+        }
+        assert!(io.push_output(b"x").is_err());
+        assert_eq!(io.out_cnt, 32);
+
+        let mut esalt = generic_io_t {
+            hash_buf: [0; 256],
+            hash_len: 4,
+            salt_buf: [0; 256],
+            salt_len: 2,
+        };
+        esalt.hash_buf[0] = u32::from_ne_bytes(*b"abcd");
+        esalt.salt_buf[0] = u32::from_ne_bytes(*b"xyzw");
+        assert_eq!(esalt.stored_hash(), b"abcd");
+        assert_eq!(esalt.salt(), b"xy");
+    }
+
+    #[test]
+    fn test_event() {
+        use std::{alloc, ffi::c_void, sync::Mutex};
+
+        static EVENTS: Mutex<Vec<(u32, String)>> = Mutex::new(vec![]);
+
+        unsafe extern "C" fn on_event(
+            id: u32,
+            hashcat_ctx: *mut hashcat_ctx_t,
+            _buf: *const c_void,
+            _len: usize,
+        ) {
+            let event_ctx = unsafe { &*(*hashcat_ctx).event_ctx };
+            assert!(event_ctx.msg_newline);
+            let msg = &event_ctx.msg_buf[..event_ctx.msg_len];
+            let msg: Vec<u8> = msg.iter().map(|&c| c as u8).collect();
+            EVENTS
+                .lock()
+                .unwrap()
+                .push((id, String::from_utf8(msg).unwrap()));
+        }
+
+        // event_ctx_t holds 176 MiB of buffers, most of which are never
+        // touched.
+        let layout = alloc::Layout::new::<event_ctx_t>();
+        let event_ctx = unsafe { alloc::alloc_zeroed(layout) } as *mut event_ctx_t;
+        assert!(!event_ctx.is_null());
+        let mut hashcat_ctx: hashcat_ctx_t = unsafe { std::mem::zeroed() };
+        hashcat_ctx.event_ctx = event_ctx;
+        hashcat_ctx.event = Some(on_event);
+
+        unsafe { event::init(&mut hashcat_ctx as *mut _ as *mut c_void, false) };
+        event::info("loaded 3 masks");
+        event::warning("mask too long");
+        unsafe { event::init(&mut hashcat_ctx as *mut _ as *mut c_void, true) };
+        event::info("hidden");
+        event::error(&"x".repeat(10000));
+        event::reset();
+        event::info("stderr only");
+        unsafe { alloc::dealloc(event_ctx as *mut u8, layout) };
+
+        let events = EVENTS.lock().unwrap();
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[0], (EVENT_LOG_INFO, "loaded 3 masks".to_string()));
+        assert_eq!(events[1], (EVENT_LOG_WARNING, "mask too long".to_string()));
+        assert_eq!(events[2].0, EVENT_LOG_ERROR);
+        assert_eq!(events[2].1.len(), HCBUFSIZ_SMALL as usize - 1);
+    }
 }
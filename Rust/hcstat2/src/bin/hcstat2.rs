@@ -0,0 +1,65 @@
+/**
+ * Author......: See docs/credits.txt
+ * License.....: MIT
+ */
+use std::{
+    env,
+    fs::File,
+    io::{self, BufRead, BufReader},
+    process,
+};
+
+use hcstat2::Hcstat2;
+
+const USAGE: &str = "usage: hcstat2 OUTFILE [WORDLIST...]
+
+Trains the Markov statistics of the wordlists, or of stdin if none is given,
+and writes them to OUTFILE in the hashcat.hcstat2 format.";
+
+fn train(stats: &mut Hcstat2, name: &str, reader: impl BufRead) -> Result<u64, String> {
+    let mut words = 0;
+    for line in reader.split(b'\n') {
+        let line = line.map_err(|e| format!("{}: {}", name, e))?;
+        let len = line
+            .iter()
+            .rposition(|&c| c != b'\r')
+            .map_or(0, |pos| pos + 1);
+        if len == 0 {
+            continue;
+        }
+        stats.train(&line[..len]);
+        words += 1;
+    }
+    Ok(words)
+}
+
+fn run(args: &[String]) -> Result<(), String> {
+    let (outfile, wordlists) = match args {
+        [outfile, wordlists @ ..] if !outfile.starts_with('-') => (outfile, wordlists),
+        _ => return Err(USAGE.to_string()),
+    };
+    let mut stats = Hcstat2::default();
+    let mut words = 0;
+    if wordlists.is_empty() {
+        words += train(&mut stats, "stdin", io::stdin().lock())?;
+    }
+    for path in wordlists {
+        let file = File::open(path).map_err(|e| format!("{}: {}", path, e))?;
+        words += train(&mut stats, path, BufReader::new(file))?;
+    }
+    stats.write(outfile)?;
+    eprintln!("Words: {}", words);
+    Ok(())
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if let Some("-h" | "--help") = args.first().map(String::as_str) {
+        println!("{}", USAGE);
+        return;
+    }
+    if let Err(e) = run(&args) {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,174 @@
+/**
+ * Author......: See docs/credits.txt
+ * License.....: MIT
+ */
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter, Read, Write},
+};
+
+use lzma_rust2::{Lzma2Options, Lzma2Reader, Lzma2Writer};
+
+#[cfg(test)]
+mod tests;
+
+pub const SP_HCSTAT: &str = "hashcat.hcstat2";
+pub const SP_VERSION: u64 = 0x6863737461740000 | 0x0002;
+pub const SP_PW_MAX: usize = 256;
+pub const CHARSIZ: usize = 256;
+pub const SP_ROOT_CNT: usize = SP_PW_MAX * CHARSIZ;
+pub const SP_MARKOV_CNT: usize = SP_PW_MAX * CHARSIZ * CHARSIZ;
+
+/// Dictionary size of the LZMA2 properties byte 0x1c hashcat decodes with.
+/// Streams must not refer further back than this.
+const DICT_SIZE: u32 = 64 << 20;
+
+/// The Markov statistics of a `.hcstat2` file: how often each character
+/// occurs at a position (root), and how often it follows another character
+/// at the previous position (markov).
+///
+/// The file is a raw LZMA2 stream of the version, a zero, then the root and
+/// markov counts, all as big-endian 64-bit integers, which is what
+/// `sp_setup_tbl()` reads.
+///
+/// Statistics may be loaded for the first positions only, since the markov
+/// counts of all positions take 128 MiB.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Hcstat2 {
+    positions: usize,
+    /// `CHARSIZ` counts per position.
+    root: Vec<u64>,
+    /// `CHARSIZ * CHARSIZ` counts per position, by previous character.
+    markov: Vec<u64>,
+}
+
+impl Default for Hcstat2 {
+    fn default() -> Self {
+        Self::with_positions(SP_PW_MAX)
+    }
+}
+
+impl Hcstat2 {
+    fn with_positions(positions: usize) -> Self {
+        Self {
+            positions,
+            root: vec![0; positions * CHARSIZ],
+            markov: vec![0; positions * CHARSIZ * CHARSIZ],
+        }
+    }
+
+    /// Counts the characters of `word`, like `hcstat2gen` from hashcat-utils.
+    /// Characters beyond the positions held are not counted.
+    pub fn train(&mut self, word: &[u8]) {
+        let word = &word[..word.len().min(self.positions)];
+        for (pos, &c) in word.iter().enumerate() {
+            self.root[pos * CHARSIZ + c as usize] += 1;
+        }
+        for (pos, pair) in word.windows(2).enumerate() {
+            self.markov[(pos * CHARSIZ + pair[0] as usize) * CHARSIZ + pair[1] as usize] += 1;
+        }
+    }
+
+    /// The number of positions held.
+    pub fn positions(&self) -> usize {
+        self.positions
+    }
+
+    /// How often each character occurs at `pos`.
+    pub fn root(&self, pos: usize) -> &[u64] {
+        &self.root[pos * CHARSIZ..(pos + 1) * CHARSIZ]
+    }
+
+    /// How often each character follows `prev` at `pos` on the next position.
+    pub fn markov(&self, pos: usize, prev: u8) -> &[u64] {
+        let start = (pos * CHARSIZ + prev as usize) * CHARSIZ;
+        &self.markov[start..start + CHARSIZ]
+    }
+
+    /// The statistics of all positions added up into a single position, as
+    /// `--markov-classic` uses them.
+    pub fn classic(&self) -> Self {
+        let mut classic = Self::with_positions(1);
+        for (table, sum) in [
+            (&self.root, &mut classic.root),
+            (&self.markov, &mut classic.markov),
+        ] {
+            for counts in table.chunks_exact(sum.len()) {
+                for (sum, &count) in sum.iter_mut().zip(counts) {
+                    *sum = sum.wrapping_add(count);
+                }
+            }
+        }
+        classic
+    }
+
+    /// Loads all positions of a `.hcstat2` file.
+    pub fn read(path: &str) -> Result<Self, String> {
+        Self::read_positions(path, SP_PW_MAX)
+    }
+
+    /// Loads the first `positions` positions of a `.hcstat2` file. The whole
+    /// file is still decoded and checked.
+    pub fn read_positions(path: &str, positions: usize) -> Result<Self, String> {
+        let file = File::open(path).map_err(|e| format!("{}: {}", path, e))?;
+        let mut reader = BufReader::new(Lzma2Reader::new(BufReader::new(file), DICT_SIZE, None));
+        let uncompress_error = |_| format!("{}: Could not uncompress data.", path);
+
+        let mut header = [0u8; 16];
+        reader.read_exact(&mut header).map_err(uncompress_error)?;
+        let v = u64::from_be_bytes(header[..8].try_into().unwrap());
+        let z = u64::from_be_bytes(header[8..].try_into().unwrap());
+        if v != SP_VERSION || z != 0 {
+            return Err(format!("{}: Invalid header", path));
+        }
+
+        let mut stats = Self::with_positions(positions.min(SP_PW_MAX));
+        let mut buf = vec![0u8; CHARSIZ * CHARSIZ * 8];
+        for table in [&mut stats.root, &mut stats.markov] {
+            let size = table.len() / stats.positions;
+            for pos in 0..SP_PW_MAX {
+                let buf = &mut buf[..size * 8];
+                reader.read_exact(buf).map_err(uncompress_error)?;
+                let Some(out) = table.chunks_exact_mut(size).nth(pos) else {
+                    continue;
+                };
+                for (out, val) in out.iter_mut().zip(buf.chunks_exact(8)) {
+                    *out = u64::from_be_bytes(val.try_into().unwrap());
+                }
+            }
+        }
+        if reader.read(&mut buf[..1]).map_err(uncompress_error)? != 0 {
+            return Err(format!("{}: Could not uncompress data.", path));
+        }
+
+        Ok(stats)
+    }
+
+    /// Writes the statistics as a `.hcstat2` file. All positions must be
+    /// held.
+    pub fn write(&self, path: &str) -> Result<(), String> {
+        if self.positions != SP_PW_MAX {
+            return Err(format!(
+                "{}: Only {} of {} positions loaded.",
+                path, self.positions, SP_PW_MAX
+            ));
+        }
+        let io_error = |e: std::io::Error| format!("{}: {}", path, e);
+        let file = File::create(path).map_err(io_error)?;
+        // The tables are mostly empty, the fastest preset compresses them
+        // nearly as well as the best one in a fraction of the time. Its
+        // dictionary is well below DICT_SIZE.
+        let options = Lzma2Options::with_preset(0);
+        let mut writer = BufWriter::new(Lzma2Writer::new(BufWriter::new(file), options));
+        writer
+            .write_all(&SP_VERSION.to_be_bytes())
+            .map_err(io_error)?;
+        writer.write_all(&0u64.to_be_bytes()).map_err(io_error)?;
+        for count in self.root.iter().chain(&self.markov) {
+            writer.write_all(&count.to_be_bytes()).map_err(io_error)?;
+        }
+        let writer = writer.into_inner().map_err(|e| io_error(e.into_error()))?;
+        let mut file = writer.finish().map_err(|e| format!("{}: {}", path, e))?;
+        file.flush().map_err(io_error)
+    }
+}
@@ -0,0 +1,88 @@
+/**
+ * Author......: See docs/credits.txt
+ * License.....: MIT
+ */
+use std::{env, fs, process};
+
+use crate::{Hcstat2, SP_PW_MAX};
+
+const REPO: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../..");
+
+fn temp_path(name: &str) -> String {
+    let path = env::temp_dir().join(format!("hcstat2_{}_{}", process::id(), name));
+    path.to_str().unwrap().to_string()
+}
+
+#[test]
+fn test_train() {
+    let mut stats = Hcstat2::default();
+    stats.train(b"abc");
+    stats.train(b"abd");
+    stats.train(b"b");
+    assert_eq!(stats.root(0)[b'a' as usize], 2);
+    assert_eq!(stats.root(0)[b'b' as usize], 1);
+    assert_eq!(stats.root(1)[b'b' as usize], 2);
+    assert_eq!(stats.root(2)[b'c' as usize], 1);
+    assert_eq!(stats.root(3).iter().sum::<u64>(), 0);
+    assert_eq!(stats.markov(0, b'a')[b'b' as usize], 2);
+    assert_eq!(stats.markov(1, b'b')[b'c' as usize], 1);
+    assert_eq!(stats.markov(1, b'b')[b'd' as usize], 1);
+    assert_eq!(stats.markov(0, b'b').iter().sum::<u64>(), 0);
+
+    stats.train(&[b'x'; SP_PW_MAX + 10]);
+    assert_eq!(stats.root(SP_PW_MAX - 1)[b'x' as usize], 1);
+    assert_eq!(stats.markov(SP_PW_MAX - 2, b'x')[b'x' as usize], 1);
+
+    let classic = stats.classic();
+    assert_eq!(classic.positions(), 1);
+    assert_eq!(classic.root(0)[b'b' as usize], 3);
+    assert_eq!(classic.root(0)[b'x' as usize], SP_PW_MAX as u64);
+    assert_eq!(classic.markov(0, b'b')[b'c' as usize], 1);
+    assert_eq!(classic.markov(0, b'x')[b'x' as usize], SP_PW_MAX as u64 - 1);
+}
+
+#[test]
+fn test_write_read() {
+    let mut stats = Hcstat2::default();
+    for word in ["password", "123456", "Password1", "letmein"] {
+        stats.train(word.as_bytes());
+    }
+    let path = temp_path("trained.hcstat2");
+    stats.write(&path).unwrap();
+    assert!(Hcstat2::read(&path).unwrap() == stats);
+
+    let prefix = Hcstat2::read_positions(&path, 2).unwrap();
+    assert_eq!(prefix.positions(), 2);
+    assert_eq!(prefix.root(1), stats.root(1));
+    assert_eq!(prefix.markov(1, b'a'), stats.markov(1, b'a'));
+    assert!(prefix.write(&path).is_err());
+    fs::remove_file(&path).unwrap();
+
+    fs::write(&path, b"not lzma2").unwrap();
+    assert_eq!(
+        Hcstat2::read(&path).err().unwrap(),
+        format!("{}: Could not uncompress data.", path)
+    );
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_shipped() {
+    let stats = Hcstat2::read(&format!("{}/hashcat.hcstat2", REPO)).unwrap();
+    let total = |pos| stats.root(pos).iter().sum::<u64>();
+    assert!(total(0) > 0);
+    assert!(total(0) >= total(8));
+    let letters = stats.root(0)[b'a' as usize..=b'z' as usize]
+        .iter()
+        .sum::<u64>();
+    assert!(letters > 0);
+    for prev in 0..=u8::MAX {
+        let follow: u64 = stats.markov(0, prev).iter().sum();
+        assert!(follow <= stats.root(0)[prev as usize]);
+    }
+
+    let path = temp_path("shipped.hcstat2");
+    stats.write(&path).unwrap();
+    assert!(Hcstat2::read(&path).unwrap() == stats);
+    fs::remove_file(&path).unwrap();
+}
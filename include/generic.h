@@ -6,7 +6,12 @@
 #ifndef HC_GENERIC_H
 #define HC_GENERIC_H
 
-#define GENERIC_PLUGIN_VERSION_REQ 712
+// Sync with:
+// Rust/hashcat-sys/src/hashcat_types.h
+
+#define GENERIC_PLUGIN_VERSION_REQ 713
+
+#define GENERIC_THREAD_NEXT_REJECT -2
 
 typedef enum generic_plugin_options
 {
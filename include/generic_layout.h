@@ -0,0 +1,38 @@
+/**
+ * Author......: See docs/credits.txt
+ * License.....: MIT
+ */
+
+#ifndef HC_GENERIC_LAYOUT_H
+#define HC_GENERIC_LAYOUT_H
+
+// Sizes and offsets of the structures hashcat shares with compiled plugins,
+// on 64-bit targets. Changing any of them is an ABI break.
+//
+// Both sides are checked against these values:
+// src/generic.c                   checks include/types.h
+// Rust/hashcat-sys/src/layout.rs  checks Rust/hashcat-sys/src/hashcat_types.h
+
+#define LAYOUT_GENERIC_GLOBAL_CTX_SIZE          312
+#define LAYOUT_GENERIC_GLOBAL_CTX_ALIGN         8
+#define LAYOUT_GENERIC_GLOBAL_CTX_QUIET         0
+#define LAYOUT_GENERIC_GLOBAL_CTX_WORKC         4
+#define LAYOUT_GENERIC_GLOBAL_CTX_WORKV         8
+#define LAYOUT_GENERIC_GLOBAL_CTX_PROFILE_DIR   16
+#define LAYOUT_GENERIC_GLOBAL_CTX_CACHE_DIR     24
+#define LAYOUT_GENERIC_GLOBAL_CTX_ERROR         32
+#define LAYOUT_GENERIC_GLOBAL_CTX_ERROR_MSG     33
+#define LAYOUT_GENERIC_GLOBAL_CTX_GBLDATA       296
+#define LAYOUT_GENERIC_GLOBAL_CTX_SHARED_DIR    304
+
+#define LAYOUT_GENERIC_THREAD_CTX_SIZE          8
+#define LAYOUT_GENERIC_THREAD_CTX_THRDATA       0
+
+#define LAYOUT_HASHCAT_CTX_EVENT_CTX            56
+#define LAYOUT_HASHCAT_CTX_EVENT                248
+
+#define LAYOUT_EVENT_CTX_MSG_BUF                167772244
+#define LAYOUT_EVENT_CTX_MSG_LEN                184549464
+#define LAYOUT_EVENT_CTX_MSG_NEWLINE            184549472
+
+#endif // HC_GENERIC_LAYOUT_H
@@ -2758,6 +2758,9 @@ typedef struct mask_ctx
 
 } mask_ctx_t;
 
+// Sync with:
+// Rust/hashcat-sys/src/hashcat_types.h
+
 typedef struct generic_global_ctx
 {
   bool   quiet;
@@ -2773,6 +2776,8 @@ typedef struct generic_global_ctx
 
   void  *gbldata; // super generic
 
+  char  *shared_dir;
+
 } generic_global_ctx_t;
 
 typedef struct generic_thread_ctx
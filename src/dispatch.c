@@ -1415,6 +1415,15 @@ static int calc (hashcat_ctx_t *hashcat_ctx, hc_device_param_t *device_param)
               break;
             }
 
+            if (pw_len == GENERIC_THREAD_NEXT_REJECT)
+            {
+              words_extra_total++;
+
+              words_extra++;
+
+              continue;
+            }
+
             pw_len = MIN (pw_len, PW_MAX);  // truncate if > 256  -- note: not rejected!
 
             if (mods == true)
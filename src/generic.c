@@ -14,6 +14,35 @@
 #include "generic.h"
 #include "dynloader.h"
 
+// Compiled plugins rely on this layout, see the same checks in
+// Rust/hashcat-sys/src/layout.rs
+
+#include "generic_layout.h"
+
+#if UINTPTR_MAX == UINT64_MAX
+_Static_assert (sizeof  (generic_global_ctx_t)              == LAYOUT_GENERIC_GLOBAL_CTX_SIZE,        "generic_global_ctx_t size changed");
+_Static_assert (_Alignof (generic_global_ctx_t)             == LAYOUT_GENERIC_GLOBAL_CTX_ALIGN,       "generic_global_ctx_t alignment changed");
+_Static_assert (offsetof (generic_global_ctx_t, quiet)       == LAYOUT_GENERIC_GLOBAL_CTX_QUIET,       "generic_global_ctx_t layout changed");
+_Static_assert (offsetof (generic_global_ctx_t, workc)       == LAYOUT_GENERIC_GLOBAL_CTX_WORKC,       "generic_global_ctx_t layout changed");
+_Static_assert (offsetof (generic_global_ctx_t, workv)       == LAYOUT_GENERIC_GLOBAL_CTX_WORKV,       "generic_global_ctx_t layout changed");
+_Static_assert (offsetof (generic_global_ctx_t, profile_dir) == LAYOUT_GENERIC_GLOBAL_CTX_PROFILE_DIR, "generic_global_ctx_t layout changed");
+_Static_assert (offsetof (generic_global_ctx_t, cache_dir)   == LAYOUT_GENERIC_GLOBAL_CTX_CACHE_DIR,   "generic_global_ctx_t layout changed");
+_Static_assert (offsetof (generic_global_ctx_t, error)       == LAYOUT_GENERIC_GLOBAL_CTX_ERROR,       "generic_global_ctx_t layout changed");
+_Static_assert (offsetof (generic_global_ctx_t, error_msg)   == LAYOUT_GENERIC_GLOBAL_CTX_ERROR_MSG,   "generic_global_ctx_t layout changed");
+_Static_assert (offsetof (generic_global_ctx_t, gbldata)     == LAYOUT_GENERIC_GLOBAL_CTX_GBLDATA,     "generic_global_ctx_t layout changed");
+_Static_assert (offsetof (generic_global_ctx_t, shared_dir)  == LAYOUT_GENERIC_GLOBAL_CTX_SHARED_DIR,  "generic_global_ctx_t layout changed");
+
+_Static_assert (sizeof  (generic_thread_ctx_t)              == LAYOUT_GENERIC_THREAD_CTX_SIZE,        "generic_thread_ctx_t size changed");
+_Static_assert (offsetof (generic_thread_ctx_t, thrdata)     == LAYOUT_GENERIC_THREAD_CTX_THRDATA,     "generic_thread_ctx_t layout changed");
+
+_Static_assert (offsetof (hashcat_ctx_t, event_ctx)          == LAYOUT_HASHCAT_CTX_EVENT_CTX,          "hashcat_ctx_t layout changed");
+_Static_assert (offsetof (hashcat_ctx_t, event)              == LAYOUT_HASHCAT_CTX_EVENT,              "hashcat_ctx_t layout changed");
+
+_Static_assert (offsetof (event_ctx_t, msg_buf)              == LAYOUT_EVENT_CTX_MSG_BUF,              "event_ctx_t layout changed");
+_Static_assert (offsetof (event_ctx_t, msg_len)              == LAYOUT_EVENT_CTX_MSG_LEN,              "event_ctx_t layout changed");
+_Static_assert (offsetof (event_ctx_t, msg_newline)          == LAYOUT_EVENT_CTX_MSG_NEWLINE,          "event_ctx_t layout changed");
+#endif
+
 bool generic_global_init (hashcat_ctx_t *hashcat_ctx)
 {
   generic_ctx_t        *generic_ctx        = hashcat_ctx->generic_ctx;
@@ -30,6 +59,7 @@ bool generic_global_init (hashcat_ctx_t *hashcat_ctx)
 
   generic_ctx->global_ctx.cache_dir   = folder_config->cache_dir;
   generic_ctx->global_ctx.profile_dir = folder_config->profile_dir;
+  generic_ctx->global_ctx.shared_dir  = folder_config->shared_dir;
 
   // ok we can also add hashcat_ctx, which might be hard to bind, but we make it optional
   // so those who support it, can have full access into hashcat core
@@ -16,6 +16,9 @@ mkdir -p $OUT
 mkdir -p $OUT/tools
 mkdir -p $OUT/Python
 mkdir -p $OUT/Rust/hashcat-sys
+mkdir -p $OUT/Rust/hashcat-bridge
+mkdir -p $OUT/Rust/hashcat-feed
+mkdir -p $OUT/Rust/hcstat2
 mkdir -p $OUT/Rust/bridges/generic_hash
 mkdir -p $OUT/Rust/bridges/dynamic_hash
 
@@ -40,6 +43,12 @@ cp -r $IN/Python/*.py                   $OUT/Python/
 cp -r $IN/Rust/hashcat-sys/src                  $OUT/Rust/hashcat-sys/
 cp    $IN/Rust/hashcat-sys/Cargo.*              $OUT/Rust/hashcat-sys/
 cp    $IN/Rust/hashcat-sys/build.rs             $OUT/Rust/hashcat-sys/
+cp -r $IN/Rust/hashcat-bridge/src               $OUT/Rust/hashcat-bridge/
+cp    $IN/Rust/hashcat-bridge/Cargo.*           $OUT/Rust/hashcat-bridge/
+cp -r $IN/Rust/hashcat-feed/src                 $OUT/Rust/hashcat-feed/
+cp    $IN/Rust/hashcat-feed/Cargo.*             $OUT/Rust/hashcat-feed/
+cp -r $IN/Rust/hcstat2/src                       $OUT/Rust/hcstat2/
+cp    $IN/Rust/hcstat2/Cargo.*                   $OUT/Rust/hcstat2/
 cp -r $IN/Rust/bridges/generic_hash/src         $OUT/Rust/bridges/generic_hash/
 cp    $IN/Rust/bridges/generic_hash/Cargo.*     $OUT/Rust/bridges/generic_hash/
 cp -r $IN/Rust/bridges/dynamic_hash/src         $OUT/Rust/bridges/dynamic_hash/